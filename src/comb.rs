@@ -0,0 +1,401 @@
+use num_complex::Complex;
+use num_traits::NumCast;
+
+use crate::{
+    first_order_iir::{IIR1Coefficients, IIR1State, IIR1},
+    units::{ZSample, FP},
+};
+
+/// Delay line length of the default `CombFilter`, enough for 20 Hz at 48 kHz
+pub const MAX_COMB_DELAY: usize = 4096;
+
+/// How a `CombFilterN` reads between samples of its delay line
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Interpolation {
+    /// Two taps. Cheap, but dulls the high end for fractional delays.
+    Linear,
+    /// First order allpass. Flat magnitude, so best inside feedback loops, but it has memory and
+    /// clicks when the delay jumps.
+    Allpass,
+    /// Third order Lagrange over four taps
+    Lagrange,
+}
+
+impl Interpolation {
+    /// Smallest delay in samples the interpolation can read from the delay line
+    pub fn min_delay<T: FP>(self) -> T {
+        match self {
+            Interpolation::Linear => T::N1,
+            Interpolation::Allpass => T::N1 + T::N0_5,
+            Interpolation::Lagrange => T::N2,
+        }
+    }
+}
+
+/// Coefficients for a feedforward or feedback comb filter with a fractional delay
+#[derive(Copy, Clone, Debug)]
+pub struct CombCoefficients<T: FP> {
+    /// Delay in samples, at least `interpolation.min_delay()`
+    pub delay: T,
+    /// Gain of the delayed signal. Feedback combs need `|gain| < 1` to be stable.
+    pub gain: T,
+    pub feedback: bool,
+    pub interpolation: Interpolation,
+    /// Filter applied to the delayed signal. Passes everything unless set with `with_damping`.
+    pub damping: IIR1Coefficients<T>,
+    /// Delay of the first tap read from the delay line
+    pub base: usize,
+    /// Tap weights for `Linear` and `Lagrange`. `Allpass` keeps its coefficient in the first.
+    pub weights: [T; 4],
+}
+
+impl<T: FP> CombCoefficients<T> {
+    pub fn get_bode_sample(&self, z: ZSample<T>) -> Complex<T> {
+        //Use y.norm() for amplitude and y.arg().to_degrees() for phase. Add to combine phase.
+
+        let z = z.pow1;
+        let delayed = self.interpolation_bode_sample(z) * self.damping.get_bode_sample(z);
+        if self.feedback {
+            Complex::new(T::N1, T::N0) / (-delayed * self.gain + T::N1)
+        } else {
+            delayed * self.gain + T::N1
+        }
+    }
+
+    fn interpolation_bode_sample(&self, z: Complex<T>) -> Complex<T> {
+        let w = self.weights;
+        let fraction = match self.interpolation {
+            Interpolation::Linear => z * w[1] + w[0],
+            Interpolation::Allpass => (z + w[0]) / (z * w[0] + T::N1),
+            Interpolation::Lagrange => ((z * w[3] + w[2]) * z + w[1]) * z + w[0],
+        };
+        fraction * z.powu(self.base as u32)
+    }
+
+    /// `y[n] = x[n] + gain * x[n - delay]`
+    pub fn feedforward(
+        delay_samples: T,
+        gain: T,
+        interpolation: Interpolation,
+    ) -> CombCoefficients<T> {
+        CombCoefficients::new(delay_samples, gain, false, interpolation)
+    }
+
+    /// `y[n] = x[n] + gain * y[n - delay]`
+    pub fn feedback(
+        delay_samples: T,
+        gain: T,
+        interpolation: Interpolation,
+    ) -> CombCoefficients<T> {
+        CombCoefficients::new(delay_samples, gain, true, interpolation)
+    }
+
+    /// A feedback comb tuned to `frequency_hz`, with a lowpass at `damping_hz` in the loop
+    /// whose phase delay at the tuned frequency is taken off the delay line.
+    /// `decay` is the loop gain, just below 1 for long strings.
+    pub fn karplus_strong(
+        frequency_hz: T,
+        decay: T,
+        damping_hz: T,
+        interpolation: Interpolation,
+        sample_rate_hz: T,
+    ) -> CombCoefficients<T> {
+        let damping = IIR1Coefficients::lowpass(damping_hz, T::N0, sample_rate_hz);
+        let omega = T::TAU() * frequency_hz / sample_rate_hz;
+        let z = ZSample::new(frequency_hz, sample_rate_hz);
+        let damping_delay = -damping.get_bode_sample(z.pow1).arg() / omega;
+        CombCoefficients::feedback(
+            sample_rate_hz / frequency_hz - damping_delay,
+            decay,
+            interpolation,
+        )
+        .with_damping(damping)
+    }
+
+    pub fn with_damping(mut self, damping: IIR1Coefficients<T>) -> CombCoefficients<T> {
+        self.damping = damping;
+        self
+    }
+
+    /// Changes the delay without touching the rest, for modulation
+    pub fn set_delay(&mut self, delay_samples: T) {
+        let delay = delay_samples.max(self.interpolation.min_delay());
+        let (base, fraction) = match self.interpolation {
+            // Keeps the fraction in 0.5..1.5, away from the pole at -1
+            Interpolation::Allpass => {
+                let base = (delay - T::N0_5).floor();
+                (base, delay - base)
+            }
+            // Keeps the fraction in 1..2, between the middle taps
+            Interpolation::Lagrange => {
+                let base = delay.floor() - T::N1;
+                (base, delay - base)
+            }
+            Interpolation::Linear => (delay.floor(), delay - delay.floor()),
+        };
+        let d = fraction;
+        self.weights = match self.interpolation {
+            Interpolation::Linear => [T::N1 - d, d, T::N0, T::N0],
+            Interpolation::Allpass => [(T::N1 - d) / (T::N1 + d), T::N0, T::N0, T::N0],
+            Interpolation::Lagrange => [
+                -(d - T::N1) * (d - T::N2) * (d - T::N3) / T::N6,
+                d * (d - T::N2) * (d - T::N3) / T::N2,
+                -d * (d - T::N1) * (d - T::N3) / T::N2,
+                d * (d - T::N1) * (d - T::N2) / T::N6,
+            ],
+        };
+        self.base = NumCast::from(base).unwrap();
+        self.delay = delay;
+    }
+
+    /// Number of samples the delay line must hold
+    pub fn max_tap(&self) -> usize {
+        match self.interpolation {
+            Interpolation::Linear | Interpolation::Allpass => self.base + 1,
+            Interpolation::Lagrange => self.base + 3,
+        }
+    }
+
+    fn new(delay_samples: T, gain: T, feedback: bool, interpolation: Interpolation) -> Self {
+        let mut coeffs = CombCoefficients {
+            delay: T::N0,
+            gain,
+            feedback,
+            interpolation,
+            damping: IIR1Coefficients {
+                a: T::N1,
+                g: T::N0,
+                a1: T::N0,
+                m0: T::N1,
+                m1: T::N0,
+            },
+            base: 0,
+            weights: [T::N0; 4],
+        };
+        coeffs.set_delay(delay_samples);
+        coeffs
+    }
+}
+
+/// Snapshot of the internal state of a `CombFilter`, including its delay line
+#[derive(Copy, Clone, Debug)]
+pub struct CombFilterStateN<T: FP, const N: usize> {
+    buffer: [T; N],
+    write: usize,
+    allpass_state: T,
+    damping: IIR1State<T>,
+}
+
+pub type CombFilterState<T> = CombFilterStateN<T, MAX_COMB_DELAY>;
+
+/// Comb filter with a delay line of `N` samples, which must be at least `coeffs.max_tap()`
+#[derive(Copy, Clone, Debug)]
+pub struct CombFilterN<T: FP, const N: usize> {
+    buffer: [T; N],
+    /// Position of the next write, which holds the sample from `N` samples ago until then
+    write: usize,
+    allpass_state: T,
+    damping: IIR1<T>,
+    pub coeffs: CombCoefficients<T>,
+}
+
+pub type CombFilter<T> = CombFilterN<T, MAX_COMB_DELAY>;
+
+impl<T: FP, const N: usize> CombFilterN<T, N> {
+    pub fn new(coefficients: CombCoefficients<T>) -> Self {
+        assert!(coefficients.max_tap() <= N);
+        CombFilterN {
+            buffer: [T::N0; N],
+            write: 0,
+            allpass_state: T::N0,
+            damping: IIR1::new(coefficients.damping),
+            coeffs: coefficients,
+        }
+    }
+
+    pub fn process(&mut self, input_sample: T) -> T {
+        let delayed = self.read();
+        let delayed = self.damping.process(delayed);
+        let output = input_sample + self.coeffs.gain * delayed;
+        self.buffer[self.write] = if self.coeffs.feedback {
+            output
+        } else {
+            input_sample
+        };
+        self.write = if self.write + 1 == N {
+            0
+        } else {
+            self.write + 1
+        };
+        output
+    }
+
+    fn tap(&self, delay: usize) -> T {
+        self.buffer[(self.write + N - delay) % N]
+    }
+
+    fn read(&mut self) -> T {
+        let base = self.coeffs.base;
+        let w = self.coeffs.weights;
+        match self.coeffs.interpolation {
+            Interpolation::Linear => w[0] * self.tap(base) + w[1] * self.tap(base + 1),
+            Interpolation::Allpass => {
+                let y = w[0] * (self.tap(base) - self.allpass_state) + self.tap(base + 1);
+                self.allpass_state = y;
+                y
+            }
+            Interpolation::Lagrange => {
+                w[0] * self.tap(base)
+                    + w[1] * self.tap(base + 1)
+                    + w[2] * self.tap(base + 2)
+                    + w[3] * self.tap(base + 3)
+            }
+        }
+    }
+
+    pub fn update_coefficients(&mut self, new_coefficients: CombCoefficients<T>) {
+        assert!(new_coefficients.max_tap() <= N);
+        self.damping.update_coefficients(new_coefficients.damping);
+        self.coeffs = new_coefficients;
+    }
+
+    /// Clears the delay line and filter memory
+    pub fn reset(&mut self) {
+        self.buffer = [T::N0; N];
+        self.allpass_state = T::N0;
+        self.damping.reset();
+    }
+
+    pub fn state(&self) -> CombFilterStateN<T, N> {
+        CombFilterStateN {
+            buffer: self.buffer,
+            write: self.write,
+            allpass_state: self.allpass_state,
+            damping: self.damping.state(),
+        }
+    }
+
+    pub fn set_state(&mut self, state: &CombFilterStateN<T, N>) {
+        self.buffer = state.buffer;
+        self.write = state.write;
+        self.allpass_state = state.allpass_state;
+        self.damping.set_state(state.damping);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rand(x: f32) -> f32 {
+        ((x * 12.9898).sin() * 43758.5453).fract()
+    }
+
+    fn impulse_response_at(
+        filter: &mut CombFilterN<f64, 64>,
+        frequency_hz: f64,
+        fs: f64,
+    ) -> Complex<f64> {
+        let z = ZSample::new(frequency_hz, fs).pow1;
+        let mut sum = Complex::new(0.0, 0.0);
+        let mut z_pow = Complex::new(1.0, 0.0);
+        for i in 0..20000 {
+            let y = filter.process(if i == 0 { 1.0 } else { 0.0 });
+            sum += z_pow * y;
+            z_pow *= z;
+        }
+        sum
+    }
+
+    #[test]
+    fn test_matches_bode() {
+        let fs = 48000.0f64;
+        let damping = IIR1Coefficients::lowpass(6000.0, 0.0, fs);
+        for interpolation in [
+            Interpolation::Linear,
+            Interpolation::Allpass,
+            Interpolation::Lagrange,
+        ]
+        .iter()
+        {
+            for coeffs in [
+                CombCoefficients::feedforward(10.3, 0.5, *interpolation),
+                CombCoefficients::feedback(10.3, 0.7, *interpolation).with_damping(damping),
+                CombCoefficients::feedback(20.8, -0.9, *interpolation),
+            ]
+            .iter()
+            {
+                let mut filter = CombFilterN::<f64, 64>::new(*coeffs);
+                for f in [100.0, 1234.0, 9000.0].iter() {
+                    filter.reset();
+                    let measured = impulse_response_at(&mut filter, *f, fs);
+                    let expected = coeffs.get_bode_sample(ZSample::new(*f, fs));
+                    assert!((measured - expected).norm() < 1e-9);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_integer_delay_is_exact() {
+        for interpolation in [
+            Interpolation::Linear,
+            Interpolation::Allpass,
+            Interpolation::Lagrange,
+        ]
+        .iter()
+        {
+            let coeffs = CombCoefficients::feedforward(7.0f32, 1.0, *interpolation);
+            let mut filter = CombFilterN::<f32, 16>::new(coeffs);
+            let input: Vec<f32> = (0..100).map(|i| rand(i as f32)).collect();
+            for i in 0..input.len() {
+                let delayed = if i >= 7 { input[i - 7] } else { 0.0 };
+                assert!((filter.process(input[i]) - input[i] - delayed).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_karplus_strong_tuning() {
+        let fs = 48000.0f64;
+        let frequency = 441.3;
+        let coeffs =
+            CombCoefficients::karplus_strong(frequency, 0.995, 5000.0, Interpolation::Lagrange, fs);
+        let loop_response = coeffs.interpolation_bode_sample(ZSample::new(frequency, fs).pow1)
+            * coeffs
+                .damping
+                .get_bode_sample(ZSample::new(frequency, fs).pow1);
+        assert!(loop_response.arg().abs() < 1e-3);
+
+        // The peak of the response is at the tuned frequency
+        let peak = (0..200)
+            .map(|i| frequency - 10.0 + i as f64 * 0.1)
+            .max_by(|a, b| {
+                let ya = coeffs.get_bode_sample(ZSample::new(*a, fs)).norm();
+                let yb = coeffs.get_bode_sample(ZSample::new(*b, fs)).norm();
+                ya.partial_cmp(&yb).unwrap()
+            })
+            .unwrap();
+        assert!((peak - frequency).abs() < 0.2);
+    }
+
+    #[test]
+    fn test_state() {
+        let damping = IIR1Coefficients::lowpass(6000.0, 0.0, 48000.0);
+        let coeffs =
+            CombCoefficients::feedback(10.3, 0.7, Interpolation::Allpass).with_damping(damping);
+        let mut filter = CombFilterN::<f32, 16>::new(coeffs);
+        for i in 0..500 {
+            filter.process(rand(i as f32));
+        }
+        let state = filter.state();
+        let first: Vec<f32> = (500..1000)
+            .map(|i| filter.process(rand(i as f32)))
+            .collect();
+        filter.set_state(&state);
+        let second: Vec<f32> = (500..1000)
+            .map(|i| filter.process(rand(i as f32)))
+            .collect();
+        assert_eq!(first, second);
+    }
+}
@@ -0,0 +1,170 @@
+use num_complex::Complex;
+
+use crate::units::FP;
+
+/// Coefficients for a one-pole/one-zero DC blocker,
+/// `y[n] = b * (x[n] - x[n-1]) + (1 - c) * y[n-1]`.
+///
+/// The pole is stored as its distance `c` from 1 rather than as `1 - c`, so cutoffs of a few
+/// hertz keep full precision in f32 even at high sample rates.
+#[derive(Copy, Clone, Debug)]
+pub struct DCBlockerCoefficients<T: FP> {
+    pub c: T,
+    pub b: T,
+}
+
+impl<T: FP> DCBlockerCoefficients<T> {
+    pub fn get_bode_sample(self, z: Complex<T>) -> Complex<T> {
+        //Use y.norm() for amplitude and y.arg().to_degrees() for phase. Add to combine phase.
+
+        // 1 - (1 - c) * z, written to avoid rounding 1 - c
+        let denominator = -z + self.c * z + T::N1;
+
+        (-z + T::N1) * self.b / denominator
+    }
+
+    /// A DC blocker with its -3dB point close to `cutoff_hz`. The gain is normalized to 1 at
+    /// nyquist.
+    pub fn new(cutoff_hz: T, sample_rate_hz: T) -> DCBlockerCoefficients<T> {
+        let c = -(-T::TAU() * cutoff_hz / sample_rate_hz).exp_m1();
+        let b = T::N1 - c * T::N0_5;
+        DCBlockerCoefficients { c, b }
+    }
+}
+
+/// Snapshot of the internal state of a `DCBlocker`
+#[derive(Copy, Clone, Debug)]
+pub struct DCBlockerState<T: FP> {
+    dc: T,
+    error: T,
+}
+
+/// Runs as `y[n] = b * (x[n] - dc)`, where `dc` follows the input through a one-pole lowpass,
+/// `dc += c * (x[n] - dc)`. The tiny steps of `dc` at low cutoffs are summed with error
+/// feedback, so the rounding of each step is carried into the next instead of piling up.
+#[derive(Copy, Clone, Debug)]
+pub struct DCBlocker<T: FP> {
+    dc: T,
+    error: T,
+    pub coeffs: DCBlockerCoefficients<T>,
+}
+
+impl<T: FP> DCBlocker<T> {
+    pub fn new(coefficients: DCBlockerCoefficients<T>) -> Self {
+        DCBlocker {
+            dc: T::N0,
+            error: T::N0,
+            coeffs: coefficients,
+        }
+    }
+
+    pub fn process(&mut self, input_sample: T) -> T {
+        let difference = input_sample - self.dc;
+        let step = self.coeffs.c * difference - self.error;
+        let dc = self.dc + step;
+        self.error = (dc - self.dc) - step;
+        self.dc = dc;
+        self.coeffs.b * difference
+    }
+
+    pub fn update_coefficients(&mut self, new_coefficients: DCBlockerCoefficients<T>) {
+        self.coeffs = new_coefficients;
+    }
+
+    /// Clears the filter memory
+    pub fn reset(&mut self) {
+        self.dc = T::N0;
+        self.error = T::N0;
+    }
+
+    /// Sets the filter memory to the steady state for a constant input of `value`
+    pub fn reset_to(&mut self, value: T) {
+        self.dc = value;
+        self.error = T::N0;
+    }
+
+    pub fn state(&self) -> DCBlockerState<T> {
+        DCBlockerState {
+            dc: self.dc,
+            error: self.error,
+        }
+    }
+
+    pub fn set_state(&mut self, state: DCBlockerState<T>) {
+        self.dc = state.dc;
+        self.error = state.error;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        first_order_iir::{IIR1Coefficients, IIR1},
+        units::{Units, ZSample},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_low_cutoff_precision() {
+        let fs = 192000.0;
+        let coeffs = DCBlockerCoefficients::<f32>::new(2.0, fs);
+        let exact = DCBlockerCoefficients::<f64>::new(2.0, fs as f64);
+        assert!((coeffs.c as f64 / exact.c - 1.0).abs() < 1e-6);
+
+        let at_cutoff = coeffs.get_bode_sample(ZSample::new(2.0, fs).pow1).norm();
+        assert!((at_cutoff.lin_to_db() + 3.0).abs() < 0.1);
+        let at_nyquist = coeffs
+            .get_bode_sample(ZSample::new(fs * 0.5, fs).pow1)
+            .norm();
+        assert!((at_nyquist - 1.0).abs() < 1e-6);
+
+        let mut filter = DCBlocker::new(coeffs);
+        let mut y = 0.0;
+        for _ in 0..(fs as usize) {
+            y = filter.process(0.5);
+        }
+        assert!(y.abs() < 1e-3);
+
+        filter.reset_to(0.25);
+        assert_eq!(filter.process(0.25), 0.0);
+    }
+
+    #[test]
+    fn test_low_cutoff_output_precision() {
+        let fs = 192000.0;
+        let mut filter = DCBlocker::new(DCBlockerCoefficients::<f32>::new(2.0, fs as f32));
+        let mut exact = DCBlocker::new(DCBlockerCoefficients::<f64>::new(2.0, fs));
+        let mut highpass = IIR1::new(IIR1Coefficients::<f32>::highpass(2.0, 0.0, fs as f32));
+        let mut exact_highpass = IIR1::new(IIR1Coefficients::<f64>::highpass(2.0, 0.0, fs));
+        let mut error = 0.0f64;
+        let mut highpass_error = 0.0f64;
+        for i in 0..(fs as usize * 4) {
+            let x = (0.5 + 0.25 * (core::f64::consts::TAU * 20.0 * i as f64 / fs).sin()) as f32;
+            let y = exact.process(x as f64);
+            error = error.max((filter.process(x) as f64 - y).abs());
+            let y = exact_highpass.process(x as f64);
+            highpass_error = highpass_error.max((highpass.process(x) as f64 - y).abs());
+        }
+        assert!(error < 2e-7);
+        assert!(error * 10.0 < highpass_error);
+    }
+
+    #[test]
+    fn test_matches_bode() {
+        let fs = 48000.0f64;
+        let coeffs = DCBlockerCoefficients::new(20.0, fs);
+        let mut filter = DCBlocker::new(coeffs);
+        for f in [5.0, 20.0, 1000.0].iter() {
+            filter.reset();
+            let z = ZSample::new(*f, fs).pow1;
+            let mut measured = Complex::new(0.0, 0.0);
+            let mut z_pow = Complex::new(1.0, 0.0);
+            for i in 0..200000 {
+                measured += z_pow * filter.process(if i == 0 { 1.0 } else { 0.0 });
+                z_pow *= z;
+            }
+            assert!((measured - coeffs.get_bode_sample(z)).norm() < 1e-9);
+        }
+    }
+}
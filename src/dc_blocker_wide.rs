@@ -0,0 +1,102 @@
+use crate::{dc_blocker::DCBlockerCoefficients, units::FP, wide_units::WIDE};
+
+#[derive(Copy, Clone, Debug)]
+pub struct WideDCBlockerCoefficients<T: WIDE> {
+    pub c: T,
+    pub b: T,
+}
+
+impl<T: WIDE> WideDCBlockerCoefficients<T> {
+    pub fn from<A: FP>(coeffs: DCBlockerCoefficients<A>) -> WideDCBlockerCoefficients<T> {
+        let c = T::from_w(coeffs.c);
+        let b = T::from_w(coeffs.b);
+        WideDCBlockerCoefficients { c, b }
+    }
+}
+
+/// Snapshot of the internal state of a `WideDCBlocker`
+#[derive(Copy, Clone, Debug)]
+pub struct WideDCBlockerState<T: WIDE> {
+    dc: T,
+    error: T,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct WideDCBlocker<T: WIDE> {
+    dc: T,
+    error: T,
+    pub coeffs: WideDCBlockerCoefficients<T>,
+}
+
+impl<T: WIDE> WideDCBlocker<T> {
+    pub fn new(coefficients: WideDCBlockerCoefficients<T>) -> Self {
+        WideDCBlocker {
+            dc: T::ZERO,
+            error: T::ZERO,
+            coeffs: coefficients,
+        }
+    }
+
+    pub fn process(&mut self, input_sample: T) -> T {
+        let difference = input_sample - self.dc;
+        let step = self.coeffs.c * difference - self.error;
+        let dc = self.dc + step;
+        self.error = (dc - self.dc) - step;
+        self.dc = dc;
+        self.coeffs.b * difference
+    }
+
+    pub fn update_coefficients(&mut self, new_coefficients: WideDCBlockerCoefficients<T>) {
+        self.coeffs = new_coefficients;
+    }
+
+    /// Clears the filter memory
+    pub fn reset(&mut self) {
+        self.dc = T::ZERO;
+        self.error = T::ZERO;
+    }
+
+    /// Sets the filter memory to the steady state for a constant input of `value`
+    pub fn reset_to(&mut self, value: T) {
+        self.dc = value;
+        self.error = T::ZERO;
+    }
+
+    pub fn state(&self) -> WideDCBlockerState<T> {
+        WideDCBlockerState {
+            dc: self.dc,
+            error: self.error,
+        }
+    }
+
+    pub fn set_state(&mut self, state: WideDCBlockerState<T>) {
+        self.dc = state.dc;
+        self.error = state.error;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wide::f32x4;
+
+    use crate::dc_blocker::DCBlocker;
+
+    use super::*;
+
+    fn rand(x: f32) -> f32 {
+        ((x * 12.9898).sin() * 43758.5453).fract()
+    }
+
+    #[test]
+    fn test_wide_dc_blocker() {
+        let coeffs = DCBlockerCoefficients::<f32>::new(5.0, 48000.0);
+        let mut filter = DCBlocker::new(coeffs);
+        let mut wide_filter = WideDCBlocker::<f32x4>::new(WideDCBlockerCoefficients::from(coeffs));
+        for i in 0..1000 {
+            let x = rand(i as f32) + 0.5;
+            let y = filter.process(x);
+            let wide_y: [f32; 4] = wide_filter.process(f32x4::splat(x)).into();
+            assert!(wide_y.iter().all(|w| (w - y).abs() < 1e-6));
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use core::fmt;
+
+use num_traits::NumCast;
+
+use crate::units::FP;
+
+/// Reasons a set of filter parameters can't be turned into coefficients
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FilterDesignError {
+    /// The order is below 1 or above the highest order supported
+    InvalidOrder,
+    /// A parameter is NaN or infinite
+    NonFiniteParameter,
+    /// The sample rate is zero or negative
+    InvalidSampleRate,
+    /// The cutoff is zero or negative
+    NonPositiveCutoff,
+    /// The cutoff is at or above half the sample rate
+    CutoffAboveNyquist,
+    /// The Q or bandwidth is zero or negative
+    NonPositiveQ,
+    /// The shelf slope is too steep to reach with the given gain
+    ShelfSlopeTooSteep,
+    /// The lower edge of a band is not below its upper edge
+    InvalidBandEdges,
+    /// The delay is too short for the order of a fractional delay filter
+    InvalidDelay,
+}
+
+impl fmt::Display for FilterDesignError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            FilterDesignError::InvalidOrder => "filter order is out of range",
+            FilterDesignError::NonFiniteParameter => "filter parameter is not finite",
+            FilterDesignError::InvalidSampleRate => "sample rate must be positive",
+            FilterDesignError::NonPositiveCutoff => "cutoff frequency must be positive",
+            FilterDesignError::CutoffAboveNyquist => "cutoff frequency must be below nyquist",
+            FilterDesignError::NonPositiveQ => "Q and bandwidth must be positive",
+            FilterDesignError::ShelfSlopeTooSteep => "shelf slope is too steep for the gain",
+            FilterDesignError::InvalidBandEdges => "lower band edge must be below upper edge",
+            FilterDesignError::InvalidDelay => "delay is too short for the filter order",
+        };
+        f.write_str(message)
+    }
+}
+
+pub(crate) fn validate_frequency<T: FP>(
+    cutoff_hz: T,
+    gain_db: T,
+    sample_rate_hz: T,
+) -> Result<(), FilterDesignError> {
+    if !(cutoff_hz.is_finite() && gain_db.is_finite() && sample_rate_hz.is_finite()) {
+        return Err(FilterDesignError::NonFiniteParameter);
+    }
+    if sample_rate_hz <= T::N0 {
+        return Err(FilterDesignError::InvalidSampleRate);
+    }
+    if cutoff_hz <= T::N0 {
+        return Err(FilterDesignError::NonPositiveCutoff);
+    }
+    if cutoff_hz >= sample_rate_hz * T::N0_5 {
+        return Err(FilterDesignError::CutoffAboveNyquist);
+    }
+    Ok(())
+}
+
+/// Also used for bandwidths, which map to Q monotonically
+pub(crate) fn validate_q<T: FP>(q_value: T) -> Result<(), FilterDesignError> {
+    if !q_value.is_finite() {
+        return Err(FilterDesignError::NonFiniteParameter);
+    }
+    if q_value <= T::N0 {
+        return Err(FilterDesignError::NonPositiveQ);
+    }
+    Ok(())
+}
+
+/// `cascade_count` is the number of second order stages available
+pub(crate) fn validate_order<T: FP>(
+    order: T,
+    cascade_count: usize,
+) -> Result<(), FilterDesignError> {
+    if !order.is_finite() {
+        return Err(FilterDesignError::NonFiniteParameter);
+    }
+    let max_order: T = NumCast::from(cascade_count * 2 + 1).unwrap();
+    if order < T::N1 || order.floor() > max_order {
+        return Err(FilterDesignError::InvalidOrder);
+    }
+    Ok(())
+}
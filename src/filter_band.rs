@@ -11,23 +11,88 @@ pub enum ProcessType {
     ProcessOddOrderCascade,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FilterType {
+    Lowpass,
+    Highpass,
+    Bandpass,
+    Notch,
+    Allpass,
+    Lowshelf,
+    Highshelf,
+    Bell,
+}
+
+/// How the bandwidth of a bell responds to its gain
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum BellCharacter {
+    /// Q sets the bandwidth at half the gain in dB, whatever the gain. A cut is the exact inverse
+    /// of the boost by the same amount. This is what `bell` designs.
+    #[default]
+    Symmetric,
+    /// Q sets the sharpness of the peak of a boost or the dip of a cut, so the bell widens as
+    /// the gain grows. Cuts are still the inverse of boosts.
+    ConstantQ,
+    /// The bandwidth narrows as the gain moves away from 0dB, like many analog console EQs.
+    /// Q sets the bandwidth of a gentle boost or cut.
+    ProportionalQ,
+}
+
 use crate::{
-    first_order_iir::{IIR1Coefficients, IIR1},
-    second_order_iir::{IIR2Coefficients, IIR2},
-    units::{Units, ZSample},
+    error::{validate_frequency, validate_order, validate_q, FilterDesignError},
+    first_order_iir::{IIR1Coefficients, IIR1State, IIR1},
+    second_order_iir::{IIR2Coefficients, IIR2State, IIR2},
+    units::{butterworth_cascade_q, Units, ZSample},
     MAX_CASCADE_COUNT,
 };
 
+/// Coefficients for a cascade of up to `N` second order stages plus one first order stage,
+/// allowing orders up to `2 * N + 1`. `N` must be at least 1. Use `FilterBandCoefficients` for
+/// the default capacity.
 #[derive(Copy, Clone, Debug)]
-pub struct FilterBandCoefficients<T: FP> {
+pub struct FilterBandCoefficientsN<T: FP, const N: usize> {
     pub iir1: IIR1Coefficients<T>,
-    pub iir2: [IIR2Coefficients<T>; MAX_CASCADE_COUNT],
+    pub iir2: [IIR2Coefficients<T>; N],
     pub process: ProcessType,
     pub iir2_cascade_count: usize,
     pub iir1_enabled: bool,
 }
 
-impl<T: FP> FilterBandCoefficients<T> {
+pub type FilterBandCoefficients<T> = FilterBandCoefficientsN<T, MAX_CASCADE_COUNT>;
+
+/// Multiplier for the Butterworth Qs that gives a cascaded shelf's second order stages the Q of
+/// a `slope` shelf with their share of the gain
+fn shelf_slope_q_scale<T: FP>(gain_db: T, slope: T, order: T) -> T {
+    let stage_gain_db = gain_db * T::N2 / order.floor().max(T::N1);
+    slope.shelf_slope_to_q(stage_gain_db) * T::SQRT_2()
+}
+
+fn validate_shelf_slope<T: FP>(
+    cutoff_hz: T,
+    gain_db: T,
+    slope: T,
+    order: T,
+    sample_rate_hz: T,
+    cascade_count: usize,
+) -> Result<(), FilterDesignError> {
+    validate_frequency(cutoff_hz, gain_db, sample_rate_hz)?;
+    validate_q(slope)?;
+    validate_order(order, cascade_count)?;
+    if order >= T::N2 && shelf_slope_q_scale(gain_db, slope, order).is_nan() {
+        return Err(FilterDesignError::ShelfSlopeTooSteep);
+    }
+    Ok(())
+}
+
+impl<T: FP, const N: usize> FilterBandCoefficientsN<T, N> {
+    /// Stops a cascade without room for any second order stage from compiling
+    const NONEMPTY: () = assert!(N > 0, "a filter band needs at least one second order stage");
+
+    fn empty_cascade() -> [IIR2Coefficients<T>; N] {
+        let () = Self::NONEMPTY;
+        IIR2Coefficients::empty_cascade_n()
+    }
+
     pub fn get_bode_sample(&self, z: ZSample<T>) -> Complex<T> {
         //Use y.norm() for amplitude and y.arg().to_degrees() for phase. Add to combine phase.
         if self.iir1_enabled {
@@ -47,13 +112,58 @@ impl<T: FP> FilterBandCoefficients<T> {
         }
     }
 
+    /// Designs a band of any type. Parameters that don't apply to `filter_type` are ignored.
+    pub fn from_type(
+        filter_type: FilterType,
+        cutoff_hz: T,
+        gain_db: T,
+        bandwidth_oct: T,
+        order: T,
+        sample_rate_hz: T,
+    ) -> FilterBandCoefficientsN<T, N> {
+        match filter_type {
+            FilterType::Lowpass => {
+                FilterBandCoefficientsN::lowpass(cutoff_hz, bandwidth_oct, order, sample_rate_hz)
+            }
+            FilterType::Highpass => {
+                FilterBandCoefficientsN::highpass(cutoff_hz, bandwidth_oct, order, sample_rate_hz)
+            }
+            FilterType::Bandpass => {
+                FilterBandCoefficientsN::bandpass(cutoff_hz, gain_db, bandwidth_oct, sample_rate_hz)
+            }
+            FilterType::Notch => {
+                FilterBandCoefficientsN::notch(cutoff_hz, gain_db, bandwidth_oct, sample_rate_hz)
+            }
+            FilterType::Allpass => {
+                FilterBandCoefficientsN::allpass(cutoff_hz, bandwidth_oct, order, sample_rate_hz)
+            }
+            FilterType::Lowshelf => FilterBandCoefficientsN::lowshelf(
+                cutoff_hz,
+                gain_db,
+                bandwidth_oct,
+                order,
+                sample_rate_hz,
+            ),
+            FilterType::Highshelf => FilterBandCoefficientsN::highshelf(
+                cutoff_hz,
+                gain_db,
+                bandwidth_oct,
+                order,
+                sample_rate_hz,
+            ),
+            FilterType::Bell => {
+                FilterBandCoefficientsN::bell(cutoff_hz, gain_db, bandwidth_oct, sample_rate_hz)
+            }
+        }
+    }
+
     pub fn lowpass(
         cutoff_hz: T,
         bandwidth_oct: T,
         order: T,
         sample_rate_hz: T,
-    ) -> FilterBandCoefficients<T> {
-        FilterBandCoefficients::filter_type_1(
+    ) -> FilterBandCoefficientsN<T, N> {
+        FilterBandCoefficientsN::filter_type_1(
             cutoff_hz,
             bandwidth_oct,
             order,
@@ -69,8 +179,8 @@ impl<T: FP> FilterBandCoefficients<T> {
         bandwidth_oct: T,
         order: T,
         sample_rate_hz: T,
-    ) -> FilterBandCoefficients<T> {
-        FilterBandCoefficients::filter_type_1(
+    ) -> FilterBandCoefficientsN<T, N> {
+        FilterBandCoefficientsN::filter_type_1(
             cutoff_hz,
             bandwidth_oct,
             order,
@@ -81,13 +191,19 @@ impl<T: FP> FilterBandCoefficients<T> {
         )
     }
 
+    /// A Butterworth highpass for removing subsonic content below `cutoff_hz`. For plain DC
+    /// removal `DCBlocker` is cheaper.
+    pub fn subsonic(cutoff_hz: T, order: T, sample_rate_hz: T) -> FilterBandCoefficientsN<T, N> {
+        FilterBandCoefficientsN::highpass(cutoff_hz, T::N1, order, sample_rate_hz)
+    }
+
     pub fn allpass(
         cutoff_hz: T,
         bandwidth_oct: T,
         order: T,
         sample_rate_hz: T,
-    ) -> FilterBandCoefficients<T> {
-        FilterBandCoefficients::filter_type_1(
+    ) -> FilterBandCoefficientsN<T, N> {
+        FilterBandCoefficientsN::filter_type_1(
             cutoff_hz,
             bandwidth_oct,
             order,
@@ -104,8 +220,8 @@ impl<T: FP> FilterBandCoefficients<T> {
         bandwidth_oct: T,
         order: T,
         sample_rate_hz: T,
-    ) -> FilterBandCoefficients<T> {
-        FilterBandCoefficients::filter_type_1(
+    ) -> FilterBandCoefficientsN<T, N> {
+        FilterBandCoefficientsN::filter_type_1(
             cutoff_hz,
             bandwidth_oct,
             order,
@@ -122,8 +238,8 @@ impl<T: FP> FilterBandCoefficients<T> {
         bandwidth_oct: T,
         order: T,
         sample_rate_hz: T,
-    ) -> FilterBandCoefficients<T> {
-        FilterBandCoefficients::filter_type_1(
+    ) -> FilterBandCoefficientsN<T, N> {
+        FilterBandCoefficientsN::filter_type_1(
             cutoff_hz,
             bandwidth_oct,
             order,
@@ -134,6 +250,147 @@ impl<T: FP> FilterBandCoefficients<T> {
         )
     }
 
+    /// A lowshelf cascade with shelf slope `slope` instead of a bandwidth. Each second order stage
+    /// gets the Q of a slope `slope` shelf of its share of the gain, spread like Butterworth Qs,
+    /// so slopes above 1 give the bump and dip of classic hardware shelves.
+    pub fn lowshelf_slope(
+        cutoff_hz: T,
+        gain_db: T,
+        slope: T,
+        order: T,
+        sample_rate_hz: T,
+    ) -> FilterBandCoefficientsN<T, N> {
+        FilterBandCoefficientsN::filter_type_q_scaled(
+            cutoff_hz,
+            shelf_slope_q_scale(gain_db, slope, order),
+            order,
+            gain_db,
+            sample_rate_hz,
+            IIR1Coefficients::lowshelf,
+            IIR2Coefficients::lowshelf,
+        )
+    }
+
+    /// A highshelf cascade with shelf slope `slope` instead of a bandwidth, see `lowshelf_slope`
+    pub fn highshelf_slope(
+        cutoff_hz: T,
+        gain_db: T,
+        slope: T,
+        order: T,
+        sample_rate_hz: T,
+    ) -> FilterBandCoefficientsN<T, N> {
+        FilterBandCoefficientsN::filter_type_q_scaled(
+            cutoff_hz,
+            shelf_slope_q_scale(gain_db, slope, order),
+            order,
+            gain_db,
+            sample_rate_hz,
+            IIR1Coefficients::highshelf,
+            IIR2Coefficients::highshelf,
+        )
+    }
+
+    /// A Butterworth lowpass cascade with a resonant peak about `resonance_db` above the passband.
+    /// Only the last second order stage resonates, so orders below 2 have no resonance.
+    pub fn lowpass_resonant(
+        cutoff_hz: T,
+        resonance_db: T,
+        order: T,
+        sample_rate_hz: T,
+    ) -> FilterBandCoefficientsN<T, N> {
+        let butterworth = FilterBandCoefficientsN::lowpass(cutoff_hz, T::N1, order, sample_rate_hz);
+        butterworth.with_resonant_last_stage(
+            cutoff_hz,
+            resonance_db,
+            sample_rate_hz,
+            IIR2Coefficients::lowpass,
+        )
+    }
+
+    /// A Butterworth highpass cascade with a resonant peak, see `lowpass_resonant`
+    pub fn highpass_resonant(
+        cutoff_hz: T,
+        resonance_db: T,
+        order: T,
+        sample_rate_hz: T,
+    ) -> FilterBandCoefficientsN<T, N> {
+        let butterworth =
+            FilterBandCoefficientsN::highpass(cutoff_hz, T::N1, order, sample_rate_hz);
+        butterworth.with_resonant_last_stage(
+            cutoff_hz,
+            resonance_db,
+            sample_rate_hz,
+            IIR2Coefficients::highpass,
+        )
+    }
+
+    /// Raises the Q of the last, highest Q, stage of a Butterworth cascade. The Butterworth Qs
+    /// multiply to the -3dB gain of the whole cascade at the cutoff, so scaling the last one
+    /// by `sqrt(2)` times the resonant Q gives the cascade the gain at the cutoff of a single
+    /// resonant stage, and leaves it Butterworth at 0dB.
+    fn with_resonant_last_stage(
+        mut self,
+        cutoff_hz: T,
+        resonance_db: T,
+        sample_rate_hz: T,
+        iir2_coeff_func: fn(T, T, T, T) -> IIR2Coefficients<T>,
+    ) -> FilterBandCoefficientsN<T, N> {
+        if self.iir2_cascade_count == 0 {
+            return self;
+        }
+        let last = self.iir2_cascade_count - 1;
+        let butterworth_q = T::N1 / self.iir2[last].k;
+        let q_value = butterworth_q * resonance_db.resonance_to_q() * T::SQRT_2();
+        self.iir2[last] = (iir2_coeff_func)(cutoff_hz, T::N0, q_value, sample_rate_hz);
+        self
+    }
+
+    /// Boosts or cuts the range between `lower_edge_hz` and `upper_edge_hz` by `gain_db` with a
+    /// flat top. Built from a highshelf at each edge, the second one undoing the first, so the
+    /// edges are at the half gain points. Each shelf is of order `order`, which must not exceed
+    /// `N`.
+    pub fn band_shelf(
+        lower_edge_hz: T,
+        upper_edge_hz: T,
+        gain_db: T,
+        order: T,
+        sample_rate_hz: T,
+    ) -> FilterBandCoefficientsN<T, N> {
+        let lower: FilterBandCoefficientsN<T, N> = FilterBandCoefficientsN::highshelf(
+            lower_edge_hz,
+            gain_db,
+            T::N1,
+            order,
+            sample_rate_hz,
+        );
+        let upper: FilterBandCoefficientsN<T, N> = FilterBandCoefficientsN::highshelf(
+            upper_edge_hz,
+            -gain_db,
+            T::N1,
+            order,
+            sample_rate_hz,
+        );
+        let shelf_count = lower.iir2_cascade_count;
+        let mut iir2_cascade_count = shelf_count * 2;
+        assert!(iir2_cascade_count + lower.iir1_enabled as usize <= N);
+        let mut iir2 = Self::empty_cascade();
+        iir2[..shelf_count].copy_from_slice(&lower.iir2[..shelf_count]);
+        iir2[shelf_count..iir2_cascade_count].copy_from_slice(&upper.iir2[..shelf_count]);
+        // The two first order stages of odd orders share one second order stage
+        if lower.iir1_enabled {
+            iir2[iir2_cascade_count] =
+                IIR2Coefficients::from_first_order_pair(lower.iir1, upper.iir1);
+            iir2_cascade_count += 1;
+        }
+        FilterBandCoefficientsN {
+            iir1: IIR1Coefficients::empty(),
+            iir2,
+            process: ProcessType::ProcessEvenOrderCascade,
+            iir2_cascade_count,
+            iir1_enabled: false,
+        }
+    }
+
     pub fn filter_type_1(
         cutoff_hz: T,
         bandwidth_oct: T,
@@ -142,18 +399,41 @@ impl<T: FP> FilterBandCoefficients<T> {
         sample_rate_hz: T,
         iir1_coeff_func: fn(T, T, T) -> IIR1Coefficients<T>,
         iir2_coeff_func: fn(T, T, T, T) -> IIR2Coefficients<T>,
-    ) -> FilterBandCoefficients<T> {
+    ) -> FilterBandCoefficientsN<T, N> {
+        // The bandwidth scales the Butterworth Qs, so it must not depend on the cutoff
+        let q_scale = bandwidth_oct.octaves_to_q() * T::FRAC_1_SQRT_2();
+        FilterBandCoefficientsN::filter_type_q_scaled(
+            cutoff_hz,
+            q_scale,
+            order,
+            gain_db,
+            sample_rate_hz,
+            iir1_coeff_func,
+            iir2_coeff_func,
+        )
+    }
+
+    /// Like `filter_type_1`, but with the Butterworth Qs multiplied by `q_scale` directly
+    pub fn filter_type_q_scaled(
+        cutoff_hz: T,
+        q_scale: T,
+        order: T,
+        gain_db: T,
+        sample_rate_hz: T,
+        iir1_coeff_func: fn(T, T, T) -> IIR1Coefficients<T>,
+        iir2_coeff_func: fn(T, T, T, T) -> IIR2Coefficients<T>,
+    ) -> FilterBandCoefficientsN<T, N> {
         let order = order.floor();
         let odd_order = order % T::N2;
         let iir1_enabled = odd_order == T::N1;
         let mut partial_gain = gain_db / order;
         let mut iir1 = IIR1Coefficients::empty();
-        let mut iir2 = [IIR2Coefficients::empty(); MAX_CASCADE_COUNT];
+        let mut iir2 = Self::empty_cascade();
         let mut process = ProcessType::ProcessIIR1Only;
         if iir1_enabled {
             iir1 = (iir1_coeff_func)(cutoff_hz, partial_gain, sample_rate_hz);
             if order <= T::N1 {
-                return FilterBandCoefficients {
+                return FilterBandCoefficientsN {
                     iir1,
                     iir2,
                     process,
@@ -166,20 +446,20 @@ impl<T: FP> FilterBandCoefficients<T> {
             process = ProcessType::ProcessEvenOrderCascade;
         }
         partial_gain = partial_gain * T::N2;
-        let q_value = bandwidth_oct.bandwidth_to_q(cutoff_hz, sample_rate_hz);
-        let q_offset = q_value * T::FRAC_1_SQRT_2(); //butterworth Q
         let iir2_cascade_count = NumCast::from((order - odd_order) / T::N2).unwrap();
         let odd_order_usize: usize = NumCast::from(odd_order).unwrap();
         let order_usize: usize = NumCast::from(order).unwrap();
-        assert!(order_usize < T::BUTTERWORTH.len());
-        assert!(odd_order_usize < T::BUTTERWORTH[0].len());
+        assert!(iir2_cascade_count <= N);
         for i in 0usize..iir2_cascade_count {
-            //let q_value: T = butterworth_cascade_q(order_usize, i + odd_order_usize);
-            let q_value = T::BUTTERWORTH[order_usize][i + odd_order_usize];
-            iir2[i] =
-                (iir2_coeff_func)(cutoff_hz, partial_gain, q_value * q_offset, sample_rate_hz);
+            // Orders past the precomputed table get their Qs computed at design time
+            let q_value = if order_usize < T::BUTTERWORTH.len() {
+                T::BUTTERWORTH[order_usize][i + odd_order_usize]
+            } else {
+                butterworth_cascade_q(order_usize, i + odd_order_usize)
+            };
+            iir2[i] = (iir2_coeff_func)(cutoff_hz, partial_gain, q_value * q_scale, sample_rate_hz);
         }
-        FilterBandCoefficients {
+        FilterBandCoefficientsN {
             iir1,
             iir2,
             process,
@@ -193,15 +473,15 @@ impl<T: FP> FilterBandCoefficients<T> {
         _gain_db: T,
         bandwidth_oct: T,
         sample_rate_hz: T,
-    ) -> FilterBandCoefficients<T> {
-        let mut iir2 = [IIR2Coefficients::empty(); MAX_CASCADE_COUNT];
+    ) -> FilterBandCoefficientsN<T, N> {
+        let mut iir2 = Self::empty_cascade();
         iir2[0] = IIR2Coefficients::notch(
             cutoff_hz,
             T::N0,
             bandwidth_oct.bandwidth_to_q(cutoff_hz, sample_rate_hz),
             sample_rate_hz,
         );
-        FilterBandCoefficients {
+        FilterBandCoefficientsN {
             iir1: IIR1Coefficients::empty(),
             iir2,
             process: ProcessType::ProcessIIR2Only,
@@ -215,15 +495,15 @@ impl<T: FP> FilterBandCoefficients<T> {
         _gain_db: T,
         bandwidth_oct: T,
         sample_rate_hz: T,
-    ) -> FilterBandCoefficients<T> {
-        let mut iir2 = [IIR2Coefficients::empty(); MAX_CASCADE_COUNT];
+    ) -> FilterBandCoefficientsN<T, N> {
+        let mut iir2 = Self::empty_cascade();
         iir2[0] = IIR2Coefficients::bandpass(
             cutoff_hz,
             T::N0,
             bandwidth_oct.bandwidth_to_q(cutoff_hz, sample_rate_hz),
             sample_rate_hz,
         );
-        FilterBandCoefficients {
+        FilterBandCoefficientsN {
             iir1: IIR1Coefficients::empty(),
             iir2,
             process: ProcessType::ProcessIIR2Only,
@@ -237,15 +517,34 @@ impl<T: FP> FilterBandCoefficients<T> {
         gain_db: T,
         bandwidth_oct: T,
         sample_rate_hz: T,
-    ) -> FilterBandCoefficients<T> {
-        let mut iir2 = [IIR2Coefficients::empty(); MAX_CASCADE_COUNT];
-        iir2[0] = IIR2Coefficients::bell(
+    ) -> FilterBandCoefficientsN<T, N> {
+        FilterBandCoefficientsN::bell_with_character(
+            cutoff_hz,
+            gain_db,
+            bandwidth_oct,
+            BellCharacter::Symmetric,
+            sample_rate_hz,
+        )
+    }
+
+    /// A bell whose bandwidth responds to gain according to `character`. The bandwidth is
+    /// measured as for `BellCharacter::Symmetric`.
+    pub fn bell_with_character(
+        cutoff_hz: T,
+        gain_db: T,
+        bandwidth_oct: T,
+        character: BellCharacter,
+        sample_rate_hz: T,
+    ) -> FilterBandCoefficientsN<T, N> {
+        let mut iir2 = Self::empty_cascade();
+        iir2[0] = IIR2Coefficients::bell_with_character(
             cutoff_hz,
             gain_db,
             bandwidth_oct.bandwidth_to_q(cutoff_hz, sample_rate_hz),
+            character,
             sample_rate_hz,
         );
-        FilterBandCoefficients {
+        FilterBandCoefficientsN {
             iir1: IIR1Coefficients::empty(),
             iir2,
             process: ProcessType::ProcessIIR2Only,
@@ -253,23 +552,277 @@ impl<T: FP> FilterBandCoefficients<T> {
             iir1_enabled: false,
         }
     }
+
+    /// Fallible version of `from_type` that checks every parameter, including the ones ignored
+    /// by `filter_type`
+    pub fn try_from_type(
+        filter_type: FilterType,
+        cutoff_hz: T,
+        gain_db: T,
+        bandwidth_oct: T,
+        order: T,
+        sample_rate_hz: T,
+    ) -> Result<FilterBandCoefficientsN<T, N>, FilterDesignError> {
+        validate_frequency(cutoff_hz, gain_db, sample_rate_hz)?;
+        validate_q(bandwidth_oct)?;
+        validate_order(order, N)?;
+        Ok(FilterBandCoefficientsN::from_type(
+            filter_type,
+            cutoff_hz,
+            gain_db,
+            bandwidth_oct,
+            order,
+            sample_rate_hz,
+        ))
+    }
+
+    pub fn try_lowpass(
+        cutoff_hz: T,
+        bandwidth_oct: T,
+        order: T,
+        sample_rate_hz: T,
+    ) -> Result<FilterBandCoefficientsN<T, N>, FilterDesignError> {
+        validate_frequency(cutoff_hz, T::N0, sample_rate_hz)?;
+        validate_q(bandwidth_oct)?;
+        validate_order(order, N)?;
+        Ok(FilterBandCoefficientsN::lowpass(
+            cutoff_hz,
+            bandwidth_oct,
+            order,
+            sample_rate_hz,
+        ))
+    }
+
+    pub fn try_highpass(
+        cutoff_hz: T,
+        bandwidth_oct: T,
+        order: T,
+        sample_rate_hz: T,
+    ) -> Result<FilterBandCoefficientsN<T, N>, FilterDesignError> {
+        validate_frequency(cutoff_hz, T::N0, sample_rate_hz)?;
+        validate_q(bandwidth_oct)?;
+        validate_order(order, N)?;
+        Ok(FilterBandCoefficientsN::highpass(
+            cutoff_hz,
+            bandwidth_oct,
+            order,
+            sample_rate_hz,
+        ))
+    }
+
+    pub fn try_allpass(
+        cutoff_hz: T,
+        bandwidth_oct: T,
+        order: T,
+        sample_rate_hz: T,
+    ) -> Result<FilterBandCoefficientsN<T, N>, FilterDesignError> {
+        validate_frequency(cutoff_hz, T::N0, sample_rate_hz)?;
+        validate_q(bandwidth_oct)?;
+        validate_order(order, N)?;
+        Ok(FilterBandCoefficientsN::allpass(
+            cutoff_hz,
+            bandwidth_oct,
+            order,
+            sample_rate_hz,
+        ))
+    }
+
+    pub fn try_lowshelf(
+        cutoff_hz: T,
+        gain_db: T,
+        bandwidth_oct: T,
+        order: T,
+        sample_rate_hz: T,
+    ) -> Result<FilterBandCoefficientsN<T, N>, FilterDesignError> {
+        validate_frequency(cutoff_hz, gain_db, sample_rate_hz)?;
+        validate_q(bandwidth_oct)?;
+        validate_order(order, N)?;
+        Ok(FilterBandCoefficientsN::lowshelf(
+            cutoff_hz,
+            gain_db,
+            bandwidth_oct,
+            order,
+            sample_rate_hz,
+        ))
+    }
+
+    pub fn try_highshelf(
+        cutoff_hz: T,
+        gain_db: T,
+        bandwidth_oct: T,
+        order: T,
+        sample_rate_hz: T,
+    ) -> Result<FilterBandCoefficientsN<T, N>, FilterDesignError> {
+        validate_frequency(cutoff_hz, gain_db, sample_rate_hz)?;
+        validate_q(bandwidth_oct)?;
+        validate_order(order, N)?;
+        Ok(FilterBandCoefficientsN::highshelf(
+            cutoff_hz,
+            gain_db,
+            bandwidth_oct,
+            order,
+            sample_rate_hz,
+        ))
+    }
+
+    pub fn try_band_shelf(
+        lower_edge_hz: T,
+        upper_edge_hz: T,
+        gain_db: T,
+        order: T,
+        sample_rate_hz: T,
+    ) -> Result<FilterBandCoefficientsN<T, N>, FilterDesignError> {
+        validate_frequency(lower_edge_hz, gain_db, sample_rate_hz)?;
+        validate_frequency(upper_edge_hz, gain_db, sample_rate_hz)?;
+        validate_order(order, N)?;
+        let max_order: T = NumCast::from(N).unwrap();
+        if order.floor() > max_order {
+            return Err(FilterDesignError::InvalidOrder);
+        }
+        if lower_edge_hz >= upper_edge_hz {
+            return Err(FilterDesignError::InvalidBandEdges);
+        }
+        Ok(FilterBandCoefficientsN::band_shelf(
+            lower_edge_hz,
+            upper_edge_hz,
+            gain_db,
+            order,
+            sample_rate_hz,
+        ))
+    }
+
+    pub fn try_lowshelf_slope(
+        cutoff_hz: T,
+        gain_db: T,
+        slope: T,
+        order: T,
+        sample_rate_hz: T,
+    ) -> Result<FilterBandCoefficientsN<T, N>, FilterDesignError> {
+        validate_shelf_slope(cutoff_hz, gain_db, slope, order, sample_rate_hz, N)?;
+        Ok(FilterBandCoefficientsN::lowshelf_slope(
+            cutoff_hz,
+            gain_db,
+            slope,
+            order,
+            sample_rate_hz,
+        ))
+    }
+
+    pub fn try_highshelf_slope(
+        cutoff_hz: T,
+        gain_db: T,
+        slope: T,
+        order: T,
+        sample_rate_hz: T,
+    ) -> Result<FilterBandCoefficientsN<T, N>, FilterDesignError> {
+        validate_shelf_slope(cutoff_hz, gain_db, slope, order, sample_rate_hz, N)?;
+        Ok(FilterBandCoefficientsN::highshelf_slope(
+            cutoff_hz,
+            gain_db,
+            slope,
+            order,
+            sample_rate_hz,
+        ))
+    }
+
+    pub fn try_notch(
+        cutoff_hz: T,
+        gain_db: T,
+        bandwidth_oct: T,
+        sample_rate_hz: T,
+    ) -> Result<FilterBandCoefficientsN<T, N>, FilterDesignError> {
+        validate_frequency(cutoff_hz, gain_db, sample_rate_hz)?;
+        validate_q(bandwidth_oct)?;
+        Ok(FilterBandCoefficientsN::notch(
+            cutoff_hz,
+            gain_db,
+            bandwidth_oct,
+            sample_rate_hz,
+        ))
+    }
+
+    pub fn try_bandpass(
+        cutoff_hz: T,
+        gain_db: T,
+        bandwidth_oct: T,
+        sample_rate_hz: T,
+    ) -> Result<FilterBandCoefficientsN<T, N>, FilterDesignError> {
+        validate_frequency(cutoff_hz, gain_db, sample_rate_hz)?;
+        validate_q(bandwidth_oct)?;
+        Ok(FilterBandCoefficientsN::bandpass(
+            cutoff_hz,
+            gain_db,
+            bandwidth_oct,
+            sample_rate_hz,
+        ))
+    }
+
+    pub fn try_bell(
+        cutoff_hz: T,
+        gain_db: T,
+        bandwidth_oct: T,
+        sample_rate_hz: T,
+    ) -> Result<FilterBandCoefficientsN<T, N>, FilterDesignError> {
+        validate_frequency(cutoff_hz, gain_db, sample_rate_hz)?;
+        validate_q(bandwidth_oct)?;
+        Ok(FilterBandCoefficientsN::bell(
+            cutoff_hz,
+            gain_db,
+            bandwidth_oct,
+            sample_rate_hz,
+        ))
+    }
+
+    pub fn try_bell_with_character(
+        cutoff_hz: T,
+        gain_db: T,
+        bandwidth_oct: T,
+        character: BellCharacter,
+        sample_rate_hz: T,
+    ) -> Result<FilterBandCoefficientsN<T, N>, FilterDesignError> {
+        validate_frequency(cutoff_hz, gain_db, sample_rate_hz)?;
+        validate_q(bandwidth_oct)?;
+        Ok(FilterBandCoefficientsN::bell_with_character(
+            cutoff_hz,
+            gain_db,
+            bandwidth_oct,
+            character,
+            sample_rate_hz,
+        ))
+    }
 }
 
+/// Snapshot of the internal state of a `FilterBand`
+#[derive(Copy, Clone, Debug)]
+pub struct FilterBandStateN<T: FP, const N: usize> {
+    iir1: IIR1State<T>,
+    iir2: [IIR2State<T>; N],
+}
+
+pub type FilterBandState<T> = FilterBandStateN<T, MAX_CASCADE_COUNT>;
+
+/// Filter cascade with room for `N` second order stages. Use `FilterBand` for the default
+/// capacity, or a smaller `N` to keep low order bands compact.
 #[derive(Copy, Clone)]
-pub struct FilterBand<T: FP> {
+pub struct FilterBandN<T: FP, const N: usize> {
     iir1: IIR1<T>,
-    iir2: [IIR2<T>; MAX_CASCADE_COUNT],
+    iir2: [IIR2<T>; N],
     iir2_cascade_count: usize,
+    process_type: ProcessType,
     pub process: fn(&mut Self, T) -> T,
 }
 
-impl<T: FP> FilterBand<T> {
-    pub fn from(coeffs: &FilterBandCoefficients<T>) -> FilterBand<T> {
-        FilterBand {
+pub type FilterBand<T> = FilterBandN<T, MAX_CASCADE_COUNT>;
+
+impl<T: FP, const N: usize> FilterBandN<T, N> {
+    pub fn from(coeffs: &FilterBandCoefficientsN<T, N>) -> FilterBandN<T, N> {
+        let () = FilterBandCoefficientsN::<T, N>::NONEMPTY;
+        FilterBandN {
             iir1: IIR1::<T>::new(coeffs.iir1),
-            iir2: [IIR2::<T>::new(coeffs.iir2[0]); MAX_CASCADE_COUNT],
+            iir2: core::array::from_fn(|i| IIR2::<T>::new(coeffs.iir2[i])),
             iir2_cascade_count: coeffs.iir2_cascade_count,
-            process: FilterBand::get_process(coeffs.process),
+            process_type: coeffs.process,
+            process: FilterBandN::get_process(coeffs.process),
         }
     }
 
@@ -301,20 +854,80 @@ impl<T: FP> FilterBand<T> {
 
     pub fn get_process(process_type: ProcessType) -> fn(&mut Self, T) -> T {
         match process_type {
-            ProcessType::ProcessIIR1Only => FilterBand::process_iir1_only,
-            ProcessType::ProcessIIR2Only => FilterBand::process_iir2_only,
-            ProcessType::ProcessEvenOrderCascade => FilterBand::process_even_order_cascade,
-            ProcessType::ProcessOddOrderCascade => FilterBand::process_odd_order_cascade,
+            ProcessType::ProcessIIR1Only => FilterBandN::process_iir1_only,
+            ProcessType::ProcessIIR2Only => FilterBandN::process_iir2_only,
+            ProcessType::ProcessEvenOrderCascade => FilterBandN::process_even_order_cascade,
+            ProcessType::ProcessOddOrderCascade => FilterBandN::process_odd_order_cascade,
         }
     }
 
-    pub fn update(&mut self, coeffs: &FilterBandCoefficients<T>) {
-        for (filter, coeff) in self.iir2.iter_mut().zip(coeffs.iir2.iter()) {
+    pub fn update(&mut self, coeffs: &FilterBandCoefficientsN<T, N>) {
+        let iir2_count = coeffs.iir2_cascade_count.max(1);
+        for (filter, coeff) in self.iir2[..iir2_count].iter_mut().zip(coeffs.iir2.iter()) {
             filter.update_coefficients(*coeff)
         }
         self.iir1.update_coefficients(coeffs.iir1);
         self.iir2_cascade_count = coeffs.iir2_cascade_count;
-        self.process = FilterBand::get_process(coeffs.process);
+        self.process_type = coeffs.process;
+        self.process = FilterBandN::get_process(coeffs.process);
+    }
+
+    /// Clears the memory of every filter in the cascade
+    pub fn reset(&mut self) {
+        self.iir1.reset();
+        for filter in self.iir2.iter_mut() {
+            filter.reset();
+        }
+    }
+
+    /// Sets the memory of every filter in the cascade to the steady state for a constant input of
+    /// `value`, with each stage settled on the output of the one before it. Same as `prime`
+    /// without the returned output.
+    pub fn reset_to(&mut self, value: T) {
+        self.prime(value);
+    }
+
+    /// Sets the memory of every filter in the cascade to the steady state for a constant input of
+    /// `input_value`, so a filter inserted into a running signal starts without a transient.
+    /// Returns the steady state output.
+    pub fn prime(&mut self, input_value: T) -> T {
+        let mut value = input_value;
+        let iir2_count = match self.process_type {
+            ProcessType::ProcessIIR1Only => 0,
+            ProcessType::ProcessIIR2Only => 1,
+            ProcessType::ProcessEvenOrderCascade | ProcessType::ProcessOddOrderCascade => {
+                self.iir2_cascade_count
+            }
+        };
+        if let ProcessType::ProcessIIR1Only | ProcessType::ProcessOddOrderCascade =
+            self.process_type
+        {
+            self.iir1.reset_to(value);
+            value = self.iir1.coeffs.dc_gain() * value;
+        }
+        for filter in self.iir2[..iir2_count].iter_mut() {
+            filter.reset_to(value);
+            value = filter.coeffs.dc_gain() * value;
+        }
+        value
+    }
+
+    pub fn state(&self) -> FilterBandStateN<T, N> {
+        let mut iir2 = [self.iir2[0].state(); N];
+        for (state, filter) in iir2.iter_mut().zip(self.iir2.iter()) {
+            *state = filter.state();
+        }
+        FilterBandStateN {
+            iir1: self.iir1.state(),
+            iir2,
+        }
+    }
+
+    pub fn set_state(&mut self, state: &FilterBandStateN<T, N>) {
+        self.iir1.set_state(state.iir1);
+        for (filter, state) in self.iir2.iter_mut().zip(state.iir2.iter()) {
+            filter.set_state(*state);
+        }
     }
 }
 
@@ -349,4 +962,287 @@ mod tests {
 
         dbg!(left[500], right[500]);
     }
+
+    #[test]
+    fn test_filter_band_state() {
+        let input: Vec<f32> = (0..1000).map(|x| rand(x as f32)).collect();
+
+        let coeffs = FilterBandCoefficients::lowpass(1000.0, 1.0, 5.0, 48000.0);
+        let mut filter = FilterBand::from(&coeffs);
+
+        for x in &input[..500] {
+            (filter.process)(&mut filter, *x);
+        }
+        let state = filter.state();
+        let first: Vec<f32> = input[500..]
+            .iter()
+            .map(|x| (filter.process)(&mut filter, *x))
+            .collect();
+
+        filter.set_state(&state);
+        let second: Vec<f32> = input[500..]
+            .iter()
+            .map(|x| (filter.process)(&mut filter, *x))
+            .collect();
+        assert_eq!(first, second);
+
+        filter.reset();
+        let mut fresh_filter = FilterBand::from(&coeffs);
+        for x in &input {
+            assert_eq!(
+                (fresh_filter.process)(&mut fresh_filter, *x),
+                (filter.process)(&mut filter, *x)
+            );
+        }
+    }
+
+    #[test]
+    fn test_resonant() {
+        let fs = 48000.0f64;
+        let peak_db = |coeffs: &FilterBandCoefficients<f64>| {
+            (0..400)
+                .map(|i| 100.0 * 1.01f64.powi(i))
+                .map(|f| {
+                    coeffs
+                        .get_bode_sample(ZSample::new(f, fs))
+                        .norm()
+                        .lin_to_db()
+                })
+                .fold(f64::MIN, f64::max)
+        };
+
+        let biquad = IIR2Coefficients::lowpass_resonant(1000.0, 12.0, fs);
+        let mut single = FilterBandCoefficients::lowpass(1000.0, 1.0, 2.0, fs);
+        single.iir2[0] = biquad;
+        assert!((peak_db(&single) - 12.0).abs() < 0.1);
+
+        for order in [2.0, 3.0, 4.0, 8.0].iter() {
+            let lowpass = FilterBandCoefficients::lowpass_resonant(1000.0, 12.0, *order, fs);
+            let highpass = FilterBandCoefficients::highpass_resonant(1000.0, 12.0, *order, fs);
+            assert!((peak_db(&lowpass) - 12.0).abs() < 0.1);
+            assert!((peak_db(&highpass) - 12.0).abs() < 0.1);
+
+            let count = lowpass.iir2_cascade_count;
+            let butterworth = FilterBandCoefficients::lowpass(1000.0, 1.0, *order, fs);
+            for i in 0..count - 1 {
+                assert_eq!(lowpass.iir2[i].k, butterworth.iir2[i].k);
+            }
+            let flat = FilterBandCoefficients::lowpass_resonant(1000.0, 0.0, *order, fs);
+            assert!(peak_db(&flat) < 0.01);
+            assert!((flat.iir2[count - 1].k - butterworth.iir2[count - 1].k).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_band_shelf() {
+        let fs = 48000.0f64;
+        let db = |coeffs: &FilterBandCoefficients<f64>, f| {
+            coeffs
+                .get_bode_sample(ZSample::new(f, fs))
+                .norm()
+                .lin_to_db()
+        };
+        for order in [1.0, 2.0, 3.0, 4.0].iter() {
+            let coeffs = FilterBandCoefficients::band_shelf(200.0, 4000.0, 9.0, *order, fs);
+            let lower = FilterBandCoefficients::highshelf(200.0, 9.0, 1.0, *order, fs);
+            let upper = FilterBandCoefficients::highshelf(4000.0, -9.0, 1.0, *order, fs);
+            for f in [20.0, 200.0, 1000.0, 4000.0, 20000.0].iter() {
+                assert!((db(&coeffs, *f) - db(&lower, *f) - db(&upper, *f)).abs() < 1e-9);
+            }
+            assert!(db(&coeffs, 1000.0) > 8.0 && db(&coeffs, 1000.0) < 9.0);
+            assert!(db(&coeffs, 5.0).abs() < 0.1);
+            assert!((db(&coeffs, 200.0) - 4.5).abs() < 0.1);
+
+            // Every stage gets its own coefficients from `from`
+            for f in [100.0, 1000.0, 15000.0].iter() {
+                let mut filter = FilterBand::from(&coeffs);
+                let squares: f64 = (0..9600)
+                    .map(|i| {
+                        let x = (core::f64::consts::TAU * f * i as f64 / fs).sin();
+                        (filter.process)(&mut filter, x)
+                    })
+                    .skip(4800)
+                    .map(|y| y * y)
+                    .sum();
+                let amplitude = (squares / 4800.0 * 2.0).sqrt();
+                assert!((amplitude.lin_to_db() - db(&coeffs, *f)).abs() < 0.01);
+            }
+        }
+
+        assert_eq!(
+            FilterBandCoefficients::try_band_shelf(4000.0, 200.0, 9.0, 2.0, fs).err(),
+            Some(FilterDesignError::InvalidBandEdges)
+        );
+        assert_eq!(
+            FilterBandCoefficientsN::<f64, 4>::try_band_shelf(200.0, 4000.0, 9.0, 5.0, fs).err(),
+            Some(FilterDesignError::InvalidOrder)
+        );
+    }
+
+    #[test]
+    fn test_shelf_slope() {
+        let fs = 48000.0f64;
+        let db = |coeffs: &FilterBandCoefficients<f64>, f| {
+            coeffs
+                .get_bode_sample(ZSample::new(f, fs))
+                .norm()
+                .lin_to_db()
+        };
+
+        let butterworth = FilterBandCoefficients::lowshelf(1000.0, 12.0, 1.0, 2.0, fs);
+        let slope = FilterBandCoefficients::lowshelf_slope(1000.0, 12.0, 1.0, 2.0, fs);
+        let biquad = IIR2Coefficients::lowshelf_slope(1000.0, 12.0, 1.0, fs);
+        for f in [100.0, 1000.0, 5000.0].iter() {
+            assert!((db(&butterworth, *f) - db(&slope, *f)).abs() < 1e-9);
+            let biquad_db = biquad
+                .get_bode_sample(ZSample::new(*f, fs))
+                .norm()
+                .lin_to_db();
+            assert!((db(&slope, *f) - biquad_db).abs() < 1e-9);
+        }
+
+        for order in [2.0, 4.0, 5.0].iter() {
+            let plain = FilterBandCoefficients::highshelf_slope(1000.0, 12.0, 1.0, *order, fs);
+            let bumpy = FilterBandCoefficients::highshelf_slope(1000.0, 12.0, 2.0, *order, fs);
+            let freqs = (0..200).map(|i| 100.0 * 1.02f64.powi(i));
+            let plain_min = freqs
+                .clone()
+                .map(|f| db(&plain, f))
+                .fold(f64::MAX, f64::min);
+            let bumpy_min = freqs
+                .clone()
+                .map(|f| db(&bumpy, f))
+                .fold(f64::MAX, f64::min);
+            let bumpy_max = freqs.map(|f| db(&bumpy, f)).fold(f64::MIN, f64::max);
+            assert!(plain_min > -0.01);
+            assert!(bumpy_min < -0.1);
+            assert!(bumpy_max > 12.1);
+        }
+
+        assert_eq!(
+            FilterBandCoefficients::try_lowshelf_slope(1000.0, 12.0, 6.0, 2.0, fs).err(),
+            Some(FilterDesignError::ShelfSlopeTooSteep)
+        );
+        assert!(FilterBandCoefficients::try_lowshelf_slope(1000.0, 12.0, 4.0, 2.0, fs).is_ok());
+    }
+
+    #[test]
+    fn test_try_constructors() {
+        let fs = 48000.0f32;
+        assert!(FilterBandCoefficients::try_lowpass(1000.0, 1.0, 4.0, fs).is_ok());
+        assert_eq!(
+            FilterBandCoefficients::try_lowpass(1000.0, 1.0, 66.0, fs).err(),
+            Some(FilterDesignError::InvalidOrder)
+        );
+        assert_eq!(
+            FilterBandCoefficients::try_highpass(1000.0, 1.0, f32::NAN, fs).err(),
+            Some(FilterDesignError::NonFiniteParameter)
+        );
+        assert_eq!(
+            FilterBandCoefficients::try_lowshelf(1000.0, 6.0, 1.0, 2.0, 0.0).err(),
+            Some(FilterDesignError::InvalidSampleRate)
+        );
+        assert_eq!(
+            FilterBandCoefficients::try_bell(24000.0, 6.0, 1.0, fs).err(),
+            Some(FilterDesignError::CutoffAboveNyquist)
+        );
+        assert_eq!(
+            FilterBandCoefficients::try_notch(1000.0, 0.0, -1.0, fs).err(),
+            Some(FilterDesignError::NonPositiveQ)
+        );
+        assert_eq!(
+            IIR2Coefficients::try_bandpass(1000.0, 0.0, 0.0, fs).err(),
+            Some(FilterDesignError::NonPositiveQ)
+        );
+        assert_eq!(
+            IIR1Coefficients::try_lowpass(-1.0, 0.0, fs).err(),
+            Some(FilterDesignError::NonPositiveCutoff)
+        );
+    }
+
+    #[test]
+    fn test_cascade_capacity() {
+        let fs = 48000.0f32;
+        let coeffs: FilterBandCoefficientsN<f32, 2> =
+            FilterBandCoefficientsN::try_lowpass(1000.0, 1.0, 5.0, fs).unwrap();
+        let mut filter = FilterBandN::from(&coeffs);
+        let mut full_filter =
+            FilterBand::from(&FilterBandCoefficients::lowpass(1000.0, 1.0, 5.0, fs));
+        for i in 0..1000 {
+            let x = rand(i as f32);
+            assert_eq!(
+                (filter.process)(&mut filter, x),
+                (full_filter.process)(&mut full_filter, x)
+            );
+        }
+
+        assert_eq!(
+            FilterBandCoefficientsN::<f32, 2>::try_lowpass(1000.0, 1.0, 6.0, fs).err(),
+            Some(FilterDesignError::InvalidOrder)
+        );
+        assert!(
+            core::mem::size_of::<FilterBandN<f32, 1>>() * 8
+                < core::mem::size_of::<FilterBand<f32>>()
+        );
+    }
+
+    #[test]
+    fn test_high_order_butterworth() {
+        let fs = 48000.0f64;
+        let cutoff = 1000.0;
+        for order in [31.0, 32.0, 64.0, 127.0, 128.0].iter() {
+            let coeffs: FilterBandCoefficientsN<f64, 64> =
+                FilterBandCoefficientsN::try_lowpass(cutoff, 1.0, *order, fs).unwrap();
+            let at_cutoff = coeffs.get_bode_sample(ZSample::new(cutoff, fs)).norm();
+            assert!((at_cutoff.lin_to_db() + 3.0103).abs() < 0.01);
+            let passband = coeffs
+                .get_bode_sample(ZSample::new(cutoff * 0.5, fs))
+                .norm();
+            assert!(passband.lin_to_db().abs() < 0.01);
+            let stopband = coeffs
+                .get_bode_sample(ZSample::new(cutoff * 1.25, fs))
+                .norm();
+            assert!(stopband.lin_to_db() < -50.0);
+        }
+        assert_eq!(
+            FilterBandCoefficientsN::<f64, 64>::try_lowpass(cutoff, 1.0, 130.0, fs).err(),
+            Some(FilterDesignError::InvalidOrder)
+        );
+    }
+
+    #[test]
+    fn test_prime() {
+        let fs = 48000.0f32;
+
+        let coeffs = FilterBandCoefficients::highpass(100.0, 1.0, 5.0, fs);
+        let mut filter = FilterBand::from(&coeffs);
+        assert_eq!(filter.prime(0.5), 0.0);
+        for _ in 0..1000 {
+            assert!((filter.process)(&mut filter, 0.5).abs() < 1e-6);
+        }
+
+        let coeffs = FilterBandCoefficients::lowshelf(1000.0, 6.0, 1.0, 3.0, fs);
+        let mut filter = FilterBand::from(&coeffs);
+        let expected = 0.5 * 6.0f32.db_to_lin();
+        assert!((filter.prime(0.5) - expected).abs() < 1e-6);
+        for _ in 0..1000 {
+            assert!(((filter.process)(&mut filter, 0.5) - expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_reset_to() {
+        // An odd order shelf settles its first order stage and every second order stage, each on
+        // the gain of the ones before it
+        let coeffs = FilterBandCoefficients::lowshelf(1000.0, 6.0, 1.0, 5.0, 48000.0f32);
+        let mut filter = FilterBand::from(&coeffs);
+        for i in 0..100 {
+            (filter.process)(&mut filter, rand(i as f32));
+        }
+        filter.reset_to(0.5);
+        let expected = 0.5 * 6.0f32.db_to_lin();
+        for _ in 0..1000 {
+            assert!(((filter.process)(&mut filter, 0.5) - expected).abs() < 1e-6);
+        }
+    }
 }
@@ -1,28 +1,34 @@
 use crate::{
-    filter_band::{FilterBandCoefficients, ProcessType},
-    first_order_iir_wide::{WideIIR1, WideIIR1Coefficients},
-    second_order_iir_wide::{WideIIR2, WideIIR2Coefficients},
+    filter_band::{FilterBandCoefficientsN, ProcessType},
+    first_order_iir_wide::{WideIIR1, WideIIR1Coefficients, WideIIR1State},
+    second_order_iir_wide::{WideIIR2, WideIIR2Coefficients, WideIIR2State},
     units::FP,
     wide_units::WIDE,
     MAX_CASCADE_COUNT,
 };
 
 #[derive(Copy, Clone, Debug)]
-pub struct WideFilterBandCoefficients<T: WIDE> {
+pub struct WideFilterBandCoefficientsN<T: WIDE, const N: usize> {
     pub iir1: WideIIR1Coefficients<T>,
-    pub iir2: [WideIIR2Coefficients<T>; MAX_CASCADE_COUNT],
+    pub iir2: [WideIIR2Coefficients<T>; N],
     pub process: ProcessType,
     pub iir2_cascade_count: usize,
     pub iir1_enabled: bool,
 }
 
-impl<T: WIDE> WideFilterBandCoefficients<T> {
-    pub fn from<A: FP>(coeffs: FilterBandCoefficients<A>) -> WideFilterBandCoefficients<T> {
-        let mut iir2_cascade = WideIIR2Coefficients::empty_cascade();
+pub type WideFilterBandCoefficients<T> = WideFilterBandCoefficientsN<T, MAX_CASCADE_COUNT>;
+
+impl<T: WIDE, const N: usize> WideFilterBandCoefficientsN<T, N> {
+    /// Stops a cascade without room for any second order stage from compiling
+    const NONEMPTY: () = assert!(N > 0, "a filter band needs at least one second order stage");
+
+    pub fn from<A: FP>(coeffs: FilterBandCoefficientsN<A, N>) -> WideFilterBandCoefficientsN<T, N> {
+        let () = Self::NONEMPTY;
+        let mut iir2_cascade = WideIIR2Coefficients::empty_cascade_n();
         for (iir2, in_iir2) in iir2_cascade.iter_mut().zip(&coeffs.iir2) {
             *iir2 = WideIIR2Coefficients::from(*in_iir2);
         }
-        WideFilterBandCoefficients {
+        WideFilterBandCoefficientsN {
             iir1: WideIIR1Coefficients::from(coeffs.iir1),
             iir2: iir2_cascade,
             process: coeffs.process,
@@ -32,21 +38,35 @@ impl<T: WIDE> WideFilterBandCoefficients<T> {
     }
 }
 
+/// Snapshot of the internal state of a `WideFilterBand`
+#[derive(Copy, Clone, Debug)]
+pub struct WideFilterBandStateN<T: WIDE, const N: usize> {
+    iir1: WideIIR1State<T>,
+    iir2: [WideIIR2State<T>; N],
+}
+
+pub type WideFilterBandState<T> = WideFilterBandStateN<T, MAX_CASCADE_COUNT>;
+
 #[derive(Copy, Clone)]
-pub struct WideFilterBand<T: WIDE> {
+pub struct WideFilterBandN<T: WIDE, const N: usize> {
     iir1: WideIIR1<T>,
-    iir2: [WideIIR2<T>; MAX_CASCADE_COUNT],
+    iir2: [WideIIR2<T>; N],
     iir2_cascade_count: usize,
+    process_type: ProcessType,
     pub process: fn(&mut Self, T) -> T,
 }
 
-impl<T: WIDE> WideFilterBand<T> {
-    pub fn from(coeffs: &WideFilterBandCoefficients<T>) -> WideFilterBand<T> {
-        WideFilterBand {
+pub type WideFilterBand<T> = WideFilterBandN<T, MAX_CASCADE_COUNT>;
+
+impl<T: WIDE, const N: usize> WideFilterBandN<T, N> {
+    pub fn from(coeffs: &WideFilterBandCoefficientsN<T, N>) -> WideFilterBandN<T, N> {
+        let () = WideFilterBandCoefficientsN::<T, N>::NONEMPTY;
+        WideFilterBandN {
             iir1: WideIIR1::new(coeffs.iir1),
-            iir2: [WideIIR2::new(coeffs.iir2[0]); MAX_CASCADE_COUNT],
+            iir2: core::array::from_fn(|i| WideIIR2::new(coeffs.iir2[i])),
             iir2_cascade_count: coeffs.iir2_cascade_count,
-            process: WideFilterBand::get_process(coeffs.process),
+            process_type: coeffs.process,
+            process: WideFilterBandN::get_process(coeffs.process),
         }
     }
 
@@ -78,20 +98,79 @@ impl<T: WIDE> WideFilterBand<T> {
 
     pub fn get_process(process_type: ProcessType) -> fn(&mut Self, T) -> T {
         match process_type {
-            ProcessType::ProcessIIR1Only => WideFilterBand::process_iir1_only,
-            ProcessType::ProcessIIR2Only => WideFilterBand::process_iir2_only,
-            ProcessType::ProcessEvenOrderCascade => WideFilterBand::process_even_order_cascade,
-            ProcessType::ProcessOddOrderCascade => WideFilterBand::process_odd_order_cascade,
+            ProcessType::ProcessIIR1Only => WideFilterBandN::process_iir1_only,
+            ProcessType::ProcessIIR2Only => WideFilterBandN::process_iir2_only,
+            ProcessType::ProcessEvenOrderCascade => WideFilterBandN::process_even_order_cascade,
+            ProcessType::ProcessOddOrderCascade => WideFilterBandN::process_odd_order_cascade,
         }
     }
 
-    pub fn update(&mut self, coeffs: &WideFilterBandCoefficients<T>) {
+    pub fn update(&mut self, coeffs: &WideFilterBandCoefficientsN<T, N>) {
         for (filter, coeff) in self.iir2.iter_mut().zip(coeffs.iir2.iter()) {
             filter.update_coefficients(*coeff)
         }
         self.iir1.update_coefficients(coeffs.iir1);
         self.iir2_cascade_count = coeffs.iir2_cascade_count;
-        self.process = WideFilterBand::get_process(coeffs.process);
+        self.process_type = coeffs.process;
+        self.process = WideFilterBandN::get_process(coeffs.process);
+    }
+
+    /// Clears the memory of every filter in the cascade
+    pub fn reset(&mut self) {
+        self.iir1.reset();
+        for filter in self.iir2.iter_mut() {
+            filter.reset();
+        }
+    }
+
+    /// Sets the memory of every filter in the cascade to the steady state for a constant input of
+    /// `value`, with each stage settled on the output of the one before it. Same as `prime`
+    /// without the returned output.
+    pub fn reset_to(&mut self, value: T) {
+        self.prime(value);
+    }
+
+    /// Sets the memory of every filter in the cascade to the steady state for a constant input of
+    /// `input_value`, so a filter inserted into a running signal starts without a transient.
+    /// Returns the steady state output.
+    pub fn prime(&mut self, input_value: T) -> T {
+        let mut value = input_value;
+        let iir2_count = match self.process_type {
+            ProcessType::ProcessIIR1Only => 0,
+            ProcessType::ProcessIIR2Only => 1,
+            ProcessType::ProcessEvenOrderCascade | ProcessType::ProcessOddOrderCascade => {
+                self.iir2_cascade_count
+            }
+        };
+        if let ProcessType::ProcessIIR1Only | ProcessType::ProcessOddOrderCascade =
+            self.process_type
+        {
+            self.iir1.reset_to(value);
+            value = self.iir1.coeffs.dc_gain() * value;
+        }
+        for filter in self.iir2[..iir2_count].iter_mut() {
+            filter.reset_to(value);
+            value = filter.coeffs.dc_gain() * value;
+        }
+        value
+    }
+
+    pub fn state(&self) -> WideFilterBandStateN<T, N> {
+        let mut iir2 = [self.iir2[0].state(); N];
+        for (state, filter) in iir2.iter_mut().zip(self.iir2.iter()) {
+            *state = filter.state();
+        }
+        WideFilterBandStateN {
+            iir1: self.iir1.state(),
+            iir2,
+        }
+    }
+
+    pub fn set_state(&mut self, state: &WideFilterBandStateN<T, N>) {
+        self.iir1.set_state(state.iir1);
+        for (filter, state) in self.iir2.iter_mut().zip(state.iir2.iter()) {
+            filter.set_state(*state);
+        }
     }
 }
 
@@ -102,6 +181,8 @@ mod tests {
     use wide::f64x2;
     use wide::f64x4;
 
+    use crate::filter_band::FilterBandCoefficients;
+
     use super::*;
 
     fn rand64(x: f64) -> f64 {
@@ -141,10 +222,10 @@ mod tests {
 
         assert_eq!(
             [
-                -1.8667512705219382,
-                2.0001785972507573,
-                1.2690387167880355,
-                -1.4693735156714782
+                -1.929670253574741,
+                2.04961283908606,
+                1.3869892348204234,
+                -1.5821900069847314
             ],
             [ch1[500], ch2[500], ch3[500], ch4[500]]
         );
@@ -173,10 +254,7 @@ mod tests {
             ch2[i] = output[1];
         }
 
-        assert_eq!(
-            [-1.8667512705219382, 2.0001785972507573],
-            [ch1[500], ch2[500]]
-        );
+        assert_eq!([-1.929670253574741, 2.04961283908606], [ch1[500], ch2[500]]);
     }
 
     #[test]
@@ -221,14 +299,8 @@ mod tests {
 
         assert_eq!(
             [
-                -0.84111476,
-                1.4498048,
-                0.048668005,
-                -1.60332,
-                0.6705888,
-                -0.72239006,
-                1.1677216,
-                1.436455
+                -0.9103136, 1.4745528, 0.1050559, -1.7089751, 0.74280125, -0.7981075, 1.1913532,
+                1.5339258
             ],
             [ch1[500], ch2[500], ch3[500], ch4[500], ch5[500], ch6[500], ch7[500], ch8[500]]
         );
@@ -262,7 +334,7 @@ mod tests {
         }
 
         assert_eq!(
-            [-0.84111476, 1.4498048, 0.048668005, -1.60332],
+            [-0.9103136, 1.4745528, 0.1050559, -1.7089751],
             [ch1[500], ch2[500], ch3[500], ch4[500]]
         );
     }
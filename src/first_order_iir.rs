@@ -1,6 +1,10 @@
 use num_complex::Complex;
 
-use crate::units::FP;
+use crate::{
+    error::{validate_frequency, FilterDesignError},
+    filter_band::FilterType,
+    units::{fast_db_to_gain, fast_prewarp, FP},
+};
 
 #[derive(Copy, Clone, Debug)]
 pub struct IIR1Coefficients<T: FP> {
@@ -22,6 +26,11 @@ impl<T: FP> IIR1Coefficients<T> {
         y
     }
 
+    /// Gain of the filter for a constant input
+    pub fn dc_gain(&self) -> T {
+        self.m0 + self.m1
+    }
+
     //TODO make const once possible
     pub fn empty() -> IIR1Coefficients<T> {
         IIR1Coefficients {
@@ -33,6 +42,46 @@ impl<T: FP> IIR1Coefficients<T> {
         }
     }
 
+    /// Recomputes the derived coefficients from `a` and `g` as stored by the constructor for
+    /// `filter_type`. Types without a first order form return `empty()`.
+    pub fn from_svf_parameters(filter_type: FilterType, a: T, g: T) -> IIR1Coefficients<T> {
+        let (m0, m1) = match filter_type {
+            FilterType::Lowpass => (T::N0, T::N1),
+            FilterType::Highpass => (T::N1, -T::N1),
+            FilterType::Allpass => (T::N1, -T::N2),
+            FilterType::Lowshelf => (T::N1, a - T::N1),
+            FilterType::Highshelf => (a, T::N1 - a),
+            FilterType::Bandpass | FilterType::Notch | FilterType::Bell => {
+                return IIR1Coefficients::empty()
+            }
+        };
+        let a1 = g / (T::N1 + g);
+        IIR1Coefficients { a, g, a1, m0, m1 }
+    }
+
+    /// Designs coefficients using `fast_prewarp` and `fast_db_to_gain` in place of `tan` and
+    /// `powf`, for modulating a filter at audio rate. See those functions for the error bounds.
+    pub fn fast(
+        filter_type: FilterType,
+        cutoff_hz: T,
+        gain_db: T,
+        sample_rate_hz: T,
+    ) -> IIR1Coefficients<T> {
+        let g = fast_prewarp(cutoff_hz, sample_rate_hz);
+        let (a, g) = match filter_type {
+            FilterType::Lowshelf => {
+                let a = fast_db_to_gain(gain_db, T::N20);
+                (a, g / a.sqrt())
+            }
+            FilterType::Highshelf => {
+                let a = fast_db_to_gain(gain_db, T::N20);
+                (a, g * a.sqrt())
+            }
+            _ => (T::N1, g),
+        };
+        IIR1Coefficients::from_svf_parameters(filter_type, a, g)
+    }
+
     pub fn lowpass(cutoff_hz: T, _gain_db: T, sample_rate_hz: T) -> IIR1Coefficients<T> {
         let cutoff_hz = cutoff_hz.min(sample_rate_hz * T::N0_5);
         let a = T::N1;
@@ -82,6 +131,77 @@ impl<T: FP> IIR1Coefficients<T> {
         let m1 = T::N1 - a;
         IIR1Coefficients { a, g, a1, m0, m1 }
     }
+
+    pub fn try_lowpass(
+        cutoff_hz: T,
+        gain_db: T,
+        sample_rate_hz: T,
+    ) -> Result<IIR1Coefficients<T>, FilterDesignError> {
+        validate_frequency(cutoff_hz, gain_db, sample_rate_hz)?;
+        Ok(IIR1Coefficients::lowpass(
+            cutoff_hz,
+            gain_db,
+            sample_rate_hz,
+        ))
+    }
+
+    pub fn try_highpass(
+        cutoff_hz: T,
+        gain_db: T,
+        sample_rate_hz: T,
+    ) -> Result<IIR1Coefficients<T>, FilterDesignError> {
+        validate_frequency(cutoff_hz, gain_db, sample_rate_hz)?;
+        Ok(IIR1Coefficients::highpass(
+            cutoff_hz,
+            gain_db,
+            sample_rate_hz,
+        ))
+    }
+
+    pub fn try_allpass(
+        cutoff_hz: T,
+        gain_db: T,
+        sample_rate_hz: T,
+    ) -> Result<IIR1Coefficients<T>, FilterDesignError> {
+        validate_frequency(cutoff_hz, gain_db, sample_rate_hz)?;
+        Ok(IIR1Coefficients::allpass(
+            cutoff_hz,
+            gain_db,
+            sample_rate_hz,
+        ))
+    }
+
+    pub fn try_lowshelf(
+        cutoff_hz: T,
+        gain_db: T,
+        sample_rate_hz: T,
+    ) -> Result<IIR1Coefficients<T>, FilterDesignError> {
+        validate_frequency(cutoff_hz, gain_db, sample_rate_hz)?;
+        Ok(IIR1Coefficients::lowshelf(
+            cutoff_hz,
+            gain_db,
+            sample_rate_hz,
+        ))
+    }
+
+    pub fn try_highshelf(
+        cutoff_hz: T,
+        gain_db: T,
+        sample_rate_hz: T,
+    ) -> Result<IIR1Coefficients<T>, FilterDesignError> {
+        validate_frequency(cutoff_hz, gain_db, sample_rate_hz)?;
+        Ok(IIR1Coefficients::highshelf(
+            cutoff_hz,
+            gain_db,
+            sample_rate_hz,
+        ))
+    }
+}
+
+/// Snapshot of the internal state of an `IIR1`
+#[derive(Copy, Clone, Debug)]
+pub struct IIR1State<T: FP> {
+    ic1eq: T,
 }
 
 /// Internal states and coefficients of the SVF form
@@ -119,4 +239,22 @@ impl<T: FP> IIR1<T> {
     pub fn update_coefficients(&mut self, new_coefficients: IIR1Coefficients<T>) {
         self.coeffs = new_coefficients;
     }
+
+    /// Clears the filter memory
+    pub fn reset(&mut self) {
+        self.ic1eq = T::N0;
+    }
+
+    /// Sets the filter memory to the steady state for a constant input of `value`
+    pub fn reset_to(&mut self, value: T) {
+        self.ic1eq = value;
+    }
+
+    pub fn state(&self) -> IIR1State<T> {
+        IIR1State { ic1eq: self.ic1eq }
+    }
+
+    pub fn set_state(&mut self, state: IIR1State<T>) {
+        self.ic1eq = state.ic1eq;
+    }
 }
@@ -1,4 +1,9 @@
-use crate::{first_order_iir::IIR1Coefficients, units::FP, wide_units::WIDE};
+use crate::{
+    filter_band::FilterType,
+    first_order_iir::IIR1Coefficients,
+    units::FP,
+    wide_units::{fast_db_to_gain_w, fast_prewarp_w, WIDE},
+};
 
 #[derive(Copy, Clone, Debug)]
 pub struct WideIIR1Coefficients<T: WIDE> {
@@ -18,6 +23,53 @@ impl<T: WIDE> WideIIR1Coefficients<T> {
         let m1 = T::from_w(coeffs.m1);
         WideIIR1Coefficients { a, g, a1, m0, m1 }
     }
+
+    /// Gain of the filter for a constant input
+    pub fn dc_gain(&self) -> T {
+        self.m0 + self.m1
+    }
+
+    /// `WIDE` version of `IIR1Coefficients::from_svf_parameters`
+    pub fn from_svf_parameters(filter_type: FilterType, a: T, g: T) -> WideIIR1Coefficients<T> {
+        let (m0, m1) = match filter_type {
+            FilterType::Lowpass => (T::N0, T::N1),
+            FilterType::Highpass => (T::N1, T::N0 - T::N1),
+            FilterType::Allpass => (T::N1, T::N0 - T::N2),
+            FilterType::Lowshelf => (T::N1, a - T::N1),
+            FilterType::Highshelf => (a, T::N1 - a),
+            FilterType::Bandpass | FilterType::Notch | FilterType::Bell => (T::N0, T::N0),
+        };
+        let a1 = g / (T::N1 + g);
+        WideIIR1Coefficients { a, g, a1, m0, m1 }
+    }
+
+    /// `WIDE` version of `IIR1Coefficients::fast`, with separate parameters for each lane
+    pub fn fast(
+        filter_type: FilterType,
+        cutoff_hz: T,
+        gain_db: T,
+        sample_rate_hz: T,
+    ) -> WideIIR1Coefficients<T> {
+        let g = fast_prewarp_w(cutoff_hz, sample_rate_hz);
+        let (a, g) = match filter_type {
+            FilterType::Lowshelf => {
+                let a = fast_db_to_gain_w(gain_db, T::N20);
+                (a, g / a.sqrt())
+            }
+            FilterType::Highshelf => {
+                let a = fast_db_to_gain_w(gain_db, T::N20);
+                (a, g * a.sqrt())
+            }
+            _ => (T::N1, g),
+        };
+        WideIIR1Coefficients::from_svf_parameters(filter_type, a, g)
+    }
+}
+
+/// Snapshot of the internal state of a `WideIIR1`
+#[derive(Copy, Clone, Debug)]
+pub struct WideIIR1State<T: WIDE> {
+    ic1eq: T,
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -53,6 +105,24 @@ impl<T: WIDE> WideIIR1<T> {
     pub fn update_coefficients(&mut self, new_coefficients: WideIIR1Coefficients<T>) {
         self.coeffs = new_coefficients;
     }
+
+    /// Clears the filter memory
+    pub fn reset(&mut self) {
+        self.ic1eq = T::ZERO;
+    }
+
+    /// Sets the filter memory to the steady state for a constant input of `value`
+    pub fn reset_to(&mut self, value: T) {
+        self.ic1eq = value;
+    }
+
+    pub fn state(&self) -> WideIIR1State<T> {
+        WideIIR1State { ic1eq: self.ic1eq }
+    }
+
+    pub fn set_state(&mut self, state: WideIIR1State<T>) {
+        self.ic1eq = state.ic1eq;
+    }
 }
 
 #[cfg(test)]
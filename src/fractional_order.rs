@@ -0,0 +1,255 @@
+use num_complex::Complex;
+use num_traits::NumCast;
+
+use crate::{
+    filter_band::{FilterBandCoefficientsN, FilterBandN, FilterType},
+    units::{ZSample, FP},
+    MAX_CASCADE_COUNT,
+};
+
+/// Coefficients for a band with a continuously variable order.
+///
+/// A fractional order is rendered as a crossfade between the two neighbouring integer orders,
+/// one of which is always even and the other odd. Keeping the even and odd orders in separate
+/// bands means that when the order crosses an integer, the band that changes structure is the
+/// one whose weight has just reached zero, so the slope can be automated without jumps.
+/// Most useful for lowpass, highpass and shelves, where the order sets the slope.
+#[derive(Copy, Clone, Debug)]
+pub struct FractionalOrderCoefficientsN<T: FP, const N: usize> {
+    pub even: FilterBandCoefficientsN<T, N>,
+    pub odd: FilterBandCoefficientsN<T, N>,
+    /// Weight of the odd order band in the output. The even band gets `1 - odd_weight`.
+    pub odd_weight: T,
+}
+
+pub type FractionalOrderCoefficients<T> = FractionalOrderCoefficientsN<T, MAX_CASCADE_COUNT>;
+
+impl<T: FP, const N: usize> FractionalOrderCoefficientsN<T, N> {
+    pub fn get_bode_sample(&self, z: ZSample<T>) -> Complex<T> {
+        let even = self.even.get_bode_sample(z);
+        let odd = self.odd.get_bode_sample(z);
+        even + (odd - even) * self.odd_weight
+    }
+
+    /// Designs a band like `FilterBandCoefficientsN::from_type`, but `order` may be any value
+    /// from 1 to `2 * N + 1`. Values outside that range are clamped.
+    pub fn from_type(
+        filter_type: FilterType,
+        cutoff_hz: T,
+        gain_db: T,
+        bandwidth_oct: T,
+        order: T,
+        sample_rate_hz: T,
+    ) -> FractionalOrderCoefficientsN<T, N> {
+        assert!(N > 0);
+        let max_order: T = NumCast::from(N * 2 + 1).unwrap();
+        let order = order.max(T::N1).min(max_order);
+        let lower_order = order.floor();
+        let fraction = order - lower_order;
+        let (odd_order, even_order, odd_weight) = if lower_order % T::N2 == T::N1 {
+            (lower_order, lower_order + T::N1, T::N1 - fraction)
+        } else {
+            (lower_order + T::N1, lower_order, fraction)
+        };
+        // At the top of the range the unused even order would need one stage too many
+        let even_order = if even_order > max_order {
+            even_order - T::N2
+        } else {
+            even_order
+        };
+        let design = |order| {
+            FilterBandCoefficientsN::from_type(
+                filter_type,
+                cutoff_hz,
+                gain_db,
+                bandwidth_oct,
+                order,
+                sample_rate_hz,
+            )
+        };
+        FractionalOrderCoefficientsN {
+            even: design(even_order),
+            odd: design(odd_order),
+            odd_weight,
+        }
+    }
+
+    pub fn lowpass(
+        cutoff_hz: T,
+        bandwidth_oct: T,
+        order: T,
+        sample_rate_hz: T,
+    ) -> FractionalOrderCoefficientsN<T, N> {
+        FractionalOrderCoefficientsN::from_type(
+            FilterType::Lowpass,
+            cutoff_hz,
+            T::N0,
+            bandwidth_oct,
+            order,
+            sample_rate_hz,
+        )
+    }
+
+    pub fn highpass(
+        cutoff_hz: T,
+        bandwidth_oct: T,
+        order: T,
+        sample_rate_hz: T,
+    ) -> FractionalOrderCoefficientsN<T, N> {
+        FractionalOrderCoefficientsN::from_type(
+            FilterType::Highpass,
+            cutoff_hz,
+            T::N0,
+            bandwidth_oct,
+            order,
+            sample_rate_hz,
+        )
+    }
+
+    pub fn lowshelf(
+        cutoff_hz: T,
+        gain_db: T,
+        bandwidth_oct: T,
+        order: T,
+        sample_rate_hz: T,
+    ) -> FractionalOrderCoefficientsN<T, N> {
+        FractionalOrderCoefficientsN::from_type(
+            FilterType::Lowshelf,
+            cutoff_hz,
+            gain_db,
+            bandwidth_oct,
+            order,
+            sample_rate_hz,
+        )
+    }
+
+    pub fn highshelf(
+        cutoff_hz: T,
+        gain_db: T,
+        bandwidth_oct: T,
+        order: T,
+        sample_rate_hz: T,
+    ) -> FractionalOrderCoefficientsN<T, N> {
+        FractionalOrderCoefficientsN::from_type(
+            FilterType::Highshelf,
+            cutoff_hz,
+            gain_db,
+            bandwidth_oct,
+            order,
+            sample_rate_hz,
+        )
+    }
+}
+
+/// Runs the even and odd order bands of a `FractionalOrderCoefficientsN` side by side and mixes
+/// their outputs. Both bands always run so either can take over without a transient.
+#[derive(Copy, Clone)]
+pub struct FractionalOrderFilterBandN<T: FP, const N: usize> {
+    pub even: FilterBandN<T, N>,
+    pub odd: FilterBandN<T, N>,
+    odd_weight: T,
+}
+
+pub type FractionalOrderFilterBand<T> = FractionalOrderFilterBandN<T, MAX_CASCADE_COUNT>;
+
+impl<T: FP, const N: usize> FractionalOrderFilterBandN<T, N> {
+    pub fn from(coeffs: &FractionalOrderCoefficientsN<T, N>) -> FractionalOrderFilterBandN<T, N> {
+        FractionalOrderFilterBandN {
+            even: FilterBandN::from(&coeffs.even),
+            odd: FilterBandN::from(&coeffs.odd),
+            odd_weight: coeffs.odd_weight,
+        }
+    }
+
+    pub fn process(&mut self, input_sample: T) -> T {
+        let even = (self.even.process)(&mut self.even, input_sample);
+        let odd = (self.odd.process)(&mut self.odd, input_sample);
+        even + (odd - even) * self.odd_weight
+    }
+
+    pub fn update(&mut self, coeffs: &FractionalOrderCoefficientsN<T, N>) {
+        self.even.update(&coeffs.even);
+        self.odd.update(&coeffs.odd);
+        self.odd_weight = coeffs.odd_weight;
+    }
+
+    pub fn reset(&mut self) {
+        self.even.reset();
+        self.odd.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{filter_band::FilterBandCoefficients, units::Units};
+
+    use super::*;
+
+    #[test]
+    fn test_integer_orders_match_filter_band() {
+        let fs = 48000.0f64;
+        for order in 1..8 {
+            let order = order as f64;
+            let coeffs = FractionalOrderCoefficients::lowpass(1000.0, 1.0, order, fs);
+            let reference = FilterBandCoefficients::lowpass(1000.0, 1.0, order, fs);
+            for f in [100.0, 1000.0, 3000.0].iter() {
+                let z = ZSample::new(*f, fs);
+                let y = coeffs.get_bode_sample(z);
+                assert!((y - reference.get_bode_sample(z)).norm() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_slope_is_continuous() {
+        let fs = 48000.0f64;
+        let z = ZSample::new(8000.0, fs);
+        let mut last = FractionalOrderCoefficients::highshelf(1000.0, -12.0, 1.0, 1.0, fs)
+            .get_bode_sample(z)
+            .norm()
+            .lin_to_db();
+        for i in 1..=400 {
+            let order = 1.0 + i as f64 * 0.01;
+            let coeffs = FractionalOrderCoefficients::highshelf(1000.0, -12.0, 1.0, order, fs);
+            let db = coeffs.get_bode_sample(z).norm().lin_to_db();
+            assert!((db - last).abs() < 0.1);
+            last = db;
+        }
+
+        let coeffs = FractionalOrderCoefficients::lowpass(1000.0, 1.0, 2.5, fs);
+        let db = coeffs
+            .get_bode_sample(ZSample::new(4000.0, fs))
+            .norm()
+            .lin_to_db();
+        let db2 = FilterBandCoefficients::lowpass(1000.0, 1.0, 2.0, fs)
+            .get_bode_sample(ZSample::new(4000.0, fs))
+            .norm()
+            .lin_to_db();
+        let db3 = FilterBandCoefficients::lowpass(1000.0, 1.0, 3.0, fs)
+            .get_bode_sample(ZSample::new(4000.0, fs))
+            .norm()
+            .lin_to_db();
+        assert!(db < db2 && db > db3);
+    }
+
+    #[test]
+    fn test_process_mixes_bands() {
+        let fs = 48000.0f64;
+        let coeffs = FractionalOrderCoefficientsN::<f64, 4>::lowpass(1000.0, 1.0, 3.3, fs);
+        for f in [300.0, 1000.0, 3000.0].iter() {
+            let mut filter = FractionalOrderFilterBandN::from(&coeffs);
+            let squares: f64 = (0..9600)
+                .map(|i| filter.process((core::f64::consts::TAU * f * i as f64 / fs).sin()))
+                .skip(4800)
+                .map(|y| y * y)
+                .sum();
+            let amplitude = (squares / 4800.0 * 2.0).sqrt();
+            let expected = coeffs.get_bode_sample(ZSample::new(*f, fs)).norm();
+            assert!((amplitude.lin_to_db() - expected.lin_to_db()).abs() < 0.01);
+        }
+
+        let top = FractionalOrderCoefficientsN::<f64, 4>::lowpass(1000.0, 1.0, 20.0, fs);
+        assert_eq!(top.odd.iir2_cascade_count, 4);
+        assert_eq!(top.odd_weight, 1.0);
+    }
+}
@@ -0,0 +1,376 @@
+use num_traits::NumCast;
+
+use crate::{
+    polyphase_iir::{AllpassSections, PolyphaseCoefficientsN, MAX_POLYPHASE_COEFFICIENTS},
+    units::FP,
+};
+
+/// Most 2x stages in an oversampling cascade, for up to 16x
+pub const MAX_OVERSAMPLING_STAGES: usize = 4;
+
+/// Snapshot of the internal state of a `HalfBandUpsampler` or `HalfBandDownsampler`
+#[derive(Copy, Clone, Debug)]
+pub struct HalfBandStateN<T: FP, const N: usize> {
+    sections: AllpassSections<T, N>,
+}
+
+pub type HalfBandState<T> = HalfBandStateN<T, MAX_POLYPHASE_COEFFICIENTS>;
+
+/// Doubles the sample rate, filtering out the image above the original Nyquist frequency
+#[derive(Copy, Clone, Debug)]
+pub struct HalfBandUpsamplerN<T: FP, const N: usize> {
+    sections: AllpassSections<T, N>,
+    pub coeffs: PolyphaseCoefficientsN<T, N>,
+}
+
+pub type HalfBandUpsampler<T> = HalfBandUpsamplerN<T, MAX_POLYPHASE_COEFFICIENTS>;
+
+impl<T: FP, const N: usize> HalfBandUpsamplerN<T, N> {
+    pub fn new(coefficients: PolyphaseCoefficientsN<T, N>) -> Self {
+        HalfBandUpsamplerN {
+            sections: AllpassSections::new(),
+            coeffs: coefficients,
+        }
+    }
+
+    /// Returns the two output samples in time order
+    pub fn process(&mut self, input_sample: T) -> [T; 2] {
+        [
+            self.sections
+                .process_chain_z1(&self.coeffs, 0, input_sample),
+            self.sections
+                .process_chain_z1(&self.coeffs, 1, input_sample),
+        ]
+    }
+
+    /// Clears the filter memory
+    pub fn reset(&mut self) {
+        self.sections.reset();
+    }
+
+    pub fn state(&self) -> HalfBandStateN<T, N> {
+        HalfBandStateN {
+            sections: self.sections,
+        }
+    }
+
+    pub fn set_state(&mut self, state: &HalfBandStateN<T, N>) {
+        self.sections = state.sections;
+    }
+}
+
+/// Halves the sample rate, filtering out everything above the new Nyquist frequency first
+#[derive(Copy, Clone, Debug)]
+pub struct HalfBandDownsamplerN<T: FP, const N: usize> {
+    sections: AllpassSections<T, N>,
+    pub coeffs: PolyphaseCoefficientsN<T, N>,
+}
+
+pub type HalfBandDownsampler<T> = HalfBandDownsamplerN<T, MAX_POLYPHASE_COEFFICIENTS>;
+
+impl<T: FP, const N: usize> HalfBandDownsamplerN<T, N> {
+    pub fn new(coefficients: PolyphaseCoefficientsN<T, N>) -> Self {
+        HalfBandDownsamplerN {
+            sections: AllpassSections::new(),
+            coeffs: coefficients,
+        }
+    }
+
+    /// Takes two input samples in time order. The output is aligned to the second.
+    pub fn process(&mut self, input: [T; 2]) -> T {
+        let a = self.sections.process_chain_z1(&self.coeffs, 0, input[1]);
+        let b = self.sections.process_chain_z1(&self.coeffs, 1, input[0]);
+        (a + b) * T::N0_5
+    }
+
+    /// Clears the filter memory
+    pub fn reset(&mut self) {
+        self.sections.reset();
+    }
+
+    pub fn state(&self) -> HalfBandStateN<T, N> {
+        HalfBandStateN {
+            sections: self.sections,
+        }
+    }
+
+    pub fn set_state(&mut self, state: &HalfBandStateN<T, N>) {
+        self.sections = state.sections;
+    }
+}
+
+/// Half-band designs for a cascade of 2x stages. Stage `k` runs between `2^k` and `2^(k + 1)`
+/// times the base sample rate. Only the first needs the full steepness, as the later ones just
+/// have to keep the base band, so their transitions widen and they get cheaper.
+#[derive(Copy, Clone, Debug)]
+pub struct OversamplingCoefficientsN<T: FP, const N: usize> {
+    pub stages: [PolyphaseCoefficientsN<T, N>; MAX_OVERSAMPLING_STAGES],
+    pub stage_count: usize,
+}
+
+pub type OversamplingCoefficients<T> = OversamplingCoefficientsN<T, MAX_POLYPHASE_COEFFICIENTS>;
+
+impl<T: FP, const N: usize> OversamplingCoefficientsN<T, N> {
+    /// `factor` is 1, 2, 4, 8 or 16. `transition` is the transition band of the first stage as
+    /// a fraction of twice the base sample rate, so the band kept is
+    /// `(0.5 - transition) * sample_rate_hz`.
+    pub fn new(factor: usize, attenuation_db: T, transition: T) -> OversamplingCoefficientsN<T, N> {
+        assert!(factor.is_power_of_two() && factor <= 1 << MAX_OVERSAMPLING_STAGES);
+        let stage_count = factor.trailing_zeros() as usize;
+        let mut stages =
+            [PolyphaseCoefficientsN::from_count(1, T::N0_5 * T::N0_5); MAX_OVERSAMPLING_STAGES];
+        let mut scale = T::N1;
+        for stage in stages[..stage_count].iter_mut() {
+            let stage_transition = T::N0_5 - (T::N0_5 - transition) / scale;
+            *stage = PolyphaseCoefficientsN::design(attenuation_db, stage_transition);
+            scale = scale * T::N2;
+        }
+        OversamplingCoefficientsN {
+            stages,
+            stage_count,
+        }
+    }
+
+    pub fn factor(&self) -> usize {
+        1 << self.stage_count
+    }
+
+    /// Delay at DC of upsampling followed by downsampling, in samples at the base rate
+    pub fn latency(&self) -> T {
+        let mut rate = T::N2;
+        let mut latency = T::N0;
+        for stage in self.stages[..self.stage_count].iter() {
+            // The downsampler keeps the second sample of each pair, one sample later at the
+            // high rate than the one the upsampler's output is aligned to
+            latency = latency + (T::N2 * stage.latency() - T::N1) / rate;
+            rate = rate * T::N2;
+        }
+        latency
+    }
+
+    /// Latency rounded to whole samples at the base rate, with the fraction that's left
+    pub fn latency_samples(&self) -> (usize, T) {
+        let latency = self.latency();
+        let samples = latency.floor();
+        (NumCast::from(samples).unwrap(), latency - samples)
+    }
+}
+
+/// Snapshot of the internal state of an `Upsampler` or `Downsampler`
+#[derive(Copy, Clone, Debug)]
+pub struct ResamplerStateN<T: FP, const N: usize> {
+    stages: [HalfBandStateN<T, N>; MAX_OVERSAMPLING_STAGES],
+}
+
+pub type ResamplerState<T> = ResamplerStateN<T, MAX_POLYPHASE_COEFFICIENTS>;
+
+/// Raises the sample rate by the factor of an `OversamplingCoefficientsN`
+#[derive(Copy, Clone, Debug)]
+pub struct UpsamplerN<T: FP, const N: usize> {
+    stages: [HalfBandUpsamplerN<T, N>; MAX_OVERSAMPLING_STAGES],
+    stage_count: usize,
+}
+
+pub type Upsampler<T> = UpsamplerN<T, MAX_POLYPHASE_COEFFICIENTS>;
+
+impl<T: FP, const N: usize> UpsamplerN<T, N> {
+    pub fn new(coeffs: &OversamplingCoefficientsN<T, N>) -> Self {
+        UpsamplerN {
+            stages: coeffs.stages.map(HalfBandUpsamplerN::new),
+            stage_count: coeffs.stage_count,
+        }
+    }
+
+    /// Fills `output`, which must hold exactly one sample per step of the factor
+    pub fn process(&mut self, input_sample: T, output: &mut [T]) {
+        assert_eq!(output.len(), 1 << self.stage_count);
+        output[0] = input_sample;
+        let mut length = 1;
+        for stage in self.stages[..self.stage_count].iter_mut() {
+            // Into a scratch buffer, as the stage needs its input in time order and the pairs
+            // would overwrite input that's still to come
+            let mut expanded = [T::N0; 1 << MAX_OVERSAMPLING_STAGES];
+            for i in 0..length {
+                let [a, b] = stage.process(output[i]);
+                expanded[2 * i] = a;
+                expanded[2 * i + 1] = b;
+            }
+            length *= 2;
+            output[..length].copy_from_slice(&expanded[..length]);
+        }
+    }
+
+    /// Clears the filter memory
+    pub fn reset(&mut self) {
+        for stage in self.stages.iter_mut() {
+            stage.reset();
+        }
+    }
+
+    pub fn state(&self) -> ResamplerStateN<T, N> {
+        ResamplerStateN {
+            stages: self.stages.map(|stage| stage.state()),
+        }
+    }
+
+    pub fn set_state(&mut self, state: &ResamplerStateN<T, N>) {
+        for (stage, state) in self.stages.iter_mut().zip(state.stages.iter()) {
+            stage.set_state(state);
+        }
+    }
+}
+
+/// Lowers the sample rate by the factor of an `OversamplingCoefficientsN`
+#[derive(Copy, Clone, Debug)]
+pub struct DownsamplerN<T: FP, const N: usize> {
+    stages: [HalfBandDownsamplerN<T, N>; MAX_OVERSAMPLING_STAGES],
+    stage_count: usize,
+}
+
+pub type Downsampler<T> = DownsamplerN<T, MAX_POLYPHASE_COEFFICIENTS>;
+
+impl<T: FP, const N: usize> DownsamplerN<T, N> {
+    pub fn new(coeffs: &OversamplingCoefficientsN<T, N>) -> Self {
+        DownsamplerN {
+            stages: coeffs.stages.map(HalfBandDownsamplerN::new),
+            stage_count: coeffs.stage_count,
+        }
+    }
+
+    /// Takes exactly one sample per step of the factor, in time order
+    pub fn process(&mut self, input: &[T]) -> T {
+        assert_eq!(input.len(), 1 << self.stage_count);
+        let mut buffer = [T::N0; 1 << MAX_OVERSAMPLING_STAGES];
+        buffer[..input.len()].copy_from_slice(input);
+        let mut length = input.len();
+        for stage in self.stages[..self.stage_count].iter_mut().rev() {
+            length /= 2;
+            for i in 0..length {
+                buffer[i] = stage.process([buffer[2 * i], buffer[2 * i + 1]]);
+            }
+        }
+        buffer[0]
+    }
+
+    /// Clears the filter memory
+    pub fn reset(&mut self) {
+        for stage in self.stages.iter_mut() {
+            stage.reset();
+        }
+    }
+
+    pub fn state(&self) -> ResamplerStateN<T, N> {
+        ResamplerStateN {
+            stages: self.stages.map(|stage| stage.state()),
+        }
+    }
+
+    pub fn set_state(&mut self, state: &ResamplerStateN<T, N>) {
+        for (stage, state) in self.stages.iter_mut().zip(state.stages.iter()) {
+            stage.set_state(state);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_complex::Complex;
+
+    use crate::{
+        polyphase_iir::PolyphaseCoefficients,
+        units::{Units, ZSample},
+    };
+
+    use super::*;
+
+    fn tone(frequency_hz: f64, fs: f64, i: usize) -> f64 {
+        (core::f64::consts::TAU * frequency_hz * i as f64 / fs).sin()
+    }
+
+    #[test]
+    fn test_half_band_stages_match_bode() {
+        let fs = 96000.0;
+        let coeffs = PolyphaseCoefficients::design(80.0f64, 0.05);
+        for f in [1000.0, 20000.0, 30000.0, 47000.0].iter() {
+            // Zero stuffed impulse through the upsampler
+            let mut up = HalfBandUpsampler::new(coeffs);
+            let z = ZSample::new(*f, fs).pow1;
+            let mut measured = Complex::new(0.0, 0.0);
+            let mut z_pow = Complex::new(1.0, 0.0);
+            for i in 0..2000 {
+                for y in up.process(if i == 0 { 1.0 } else { 0.0 }).iter() {
+                    measured += z_pow * *y;
+                    z_pow *= z;
+                }
+            }
+            let expected = coeffs.get_bode_sample(ZSample::new(*f, fs)) * 2.0;
+            assert!((measured - expected).norm() < 1e-9);
+        }
+
+        // Content above a quarter of the rate is gone after downsampling
+        let mut down = HalfBandDownsampler::new(coeffs);
+        let mut peak = 0.0f64;
+        for i in 0..4000 {
+            let y = down.process([tone(30000.0, fs, 2 * i), tone(30000.0, fs, 2 * i + 1)]);
+            if i > 1000 {
+                peak = peak.max(y.abs());
+            }
+        }
+        assert!(peak.lin_to_db() < -80.0);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let fs = 48000.0;
+        for factor in [1, 2, 4, 8, 16].iter() {
+            let coeffs = OversamplingCoefficients::new(*factor, 100.0f64, 0.04);
+            assert_eq!(coeffs.factor(), *factor);
+            let mut up = Upsampler::new(&coeffs);
+            let mut down = Downsampler::new(&coeffs);
+            let mut buffer = [0.0; 16];
+            let latency = coeffs.latency();
+            // A low tone comes back delayed by the latency
+            for i in 0..4800 {
+                up.process(tone(100.0, fs, i), &mut buffer[..*factor]);
+                let y = down.process(&buffer[..*factor]);
+                if i > 2000 {
+                    let t = i as f64 - latency;
+                    let expected = (core::f64::consts::TAU * 100.0 * t / fs).sin();
+                    assert!((y - expected).abs() < 1e-4);
+                }
+            }
+        }
+
+        // Later stages of a cascade need fewer coefficients
+        let coeffs = OversamplingCoefficients::new(16, 100.0f64, 0.04);
+        for k in 1..4 {
+            assert!(coeffs.stages[k].count < coeffs.stages[k - 1].count);
+        }
+    }
+
+    fn round_trip(up: &mut Upsampler<f64>, down: &mut Downsampler<f64>, i: usize) -> f64 {
+        let mut buffer = [0.0; 8];
+        up.process(tone(1000.0, 48000.0, i), &mut buffer);
+        down.process(&buffer)
+    }
+
+    #[test]
+    fn test_state() {
+        let coeffs = OversamplingCoefficients::new(8, 90.0f64, 0.05);
+        let mut up = Upsampler::new(&coeffs);
+        let mut down = Downsampler::new(&coeffs);
+        for i in 0..500 {
+            round_trip(&mut up, &mut down, i);
+        }
+        let (up_state, down_state) = (up.state(), down.state());
+        let first: Vec<f64> = (500..1000)
+            .map(|i| round_trip(&mut up, &mut down, i))
+            .collect();
+        up.set_state(&up_state);
+        down.set_state(&down_state);
+        let second: Vec<f64> = (500..1000)
+            .map(|i| round_trip(&mut up, &mut down, i))
+            .collect();
+        assert_eq!(first, second);
+    }
+}
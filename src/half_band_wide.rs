@@ -0,0 +1,341 @@
+use crate::{
+    half_band::{OversamplingCoefficientsN, MAX_OVERSAMPLING_STAGES},
+    polyphase_iir::{PolyphaseCoefficientsN, MAX_POLYPHASE_COEFFICIENTS},
+    units::FP,
+    wide_units::WIDE,
+};
+
+#[derive(Copy, Clone, Debug)]
+pub struct WidePolyphaseCoefficientsN<T: WIDE, const N: usize> {
+    pub coefficients: [T; N],
+    pub count: usize,
+}
+
+pub type WidePolyphaseCoefficients<T> = WidePolyphaseCoefficientsN<T, MAX_POLYPHASE_COEFFICIENTS>;
+
+impl<T: WIDE, const N: usize> WidePolyphaseCoefficientsN<T, N> {
+    pub fn from<A: FP>(coeffs: PolyphaseCoefficientsN<A, N>) -> WidePolyphaseCoefficientsN<T, N> {
+        WidePolyphaseCoefficientsN {
+            coefficients: coeffs.coefficients.map(T::from_w),
+            count: coeffs.count,
+        }
+    }
+}
+
+/// `WIDE` mirror of the memory of `polyphase_iir::AllpassSections`, which only holds `FP`
+/// samples. Only the half-band resamplers need it, so it keeps just their `z^-1` chain.
+#[derive(Copy, Clone, Debug)]
+struct WidePolyphaseSections<T: WIDE, const N: usize> {
+    x: [T; N],
+    y: [T; N],
+}
+
+impl<T: WIDE, const N: usize> WidePolyphaseSections<T, N> {
+    fn new() -> Self {
+        WidePolyphaseSections {
+            x: [T::ZERO; N],
+            y: [T::ZERO; N],
+        }
+    }
+
+    /// Same as `AllpassSections::process_chain_z1`
+    fn process_chain_z1(
+        &mut self,
+        coeffs: &WidePolyphaseCoefficientsN<T, N>,
+        first: usize,
+        input_sample: T,
+    ) -> T {
+        let mut x = input_sample;
+        for i in (first..coeffs.count).step_by(2) {
+            let y = coeffs.coefficients[i] * (x - self.y[i]) + self.x[i];
+            self.x[i] = x;
+            self.y[i] = y;
+            x = y;
+        }
+        x
+    }
+}
+
+/// Snapshot of the internal state of a `WideHalfBandUpsampler` or `WideHalfBandDownsampler`
+#[derive(Copy, Clone, Debug)]
+pub struct WideHalfBandStateN<T: WIDE, const N: usize> {
+    sections: WidePolyphaseSections<T, N>,
+}
+
+pub type WideHalfBandState<T> = WideHalfBandStateN<T, MAX_POLYPHASE_COEFFICIENTS>;
+
+#[derive(Copy, Clone, Debug)]
+pub struct WideHalfBandUpsamplerN<T: WIDE, const N: usize> {
+    sections: WidePolyphaseSections<T, N>,
+    pub coeffs: WidePolyphaseCoefficientsN<T, N>,
+}
+
+pub type WideHalfBandUpsampler<T> = WideHalfBandUpsamplerN<T, MAX_POLYPHASE_COEFFICIENTS>;
+
+impl<T: WIDE, const N: usize> WideHalfBandUpsamplerN<T, N> {
+    pub fn new(coefficients: WidePolyphaseCoefficientsN<T, N>) -> Self {
+        WideHalfBandUpsamplerN {
+            sections: WidePolyphaseSections::new(),
+            coeffs: coefficients,
+        }
+    }
+
+    /// Returns the two output samples in time order
+    pub fn process(&mut self, input_sample: T) -> [T; 2] {
+        [
+            self.sections
+                .process_chain_z1(&self.coeffs, 0, input_sample),
+            self.sections
+                .process_chain_z1(&self.coeffs, 1, input_sample),
+        ]
+    }
+
+    /// Clears the filter memory
+    pub fn reset(&mut self) {
+        self.sections = WidePolyphaseSections::new();
+    }
+
+    pub fn state(&self) -> WideHalfBandStateN<T, N> {
+        WideHalfBandStateN {
+            sections: self.sections,
+        }
+    }
+
+    pub fn set_state(&mut self, state: &WideHalfBandStateN<T, N>) {
+        self.sections = state.sections;
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct WideHalfBandDownsamplerN<T: WIDE, const N: usize> {
+    sections: WidePolyphaseSections<T, N>,
+    pub coeffs: WidePolyphaseCoefficientsN<T, N>,
+}
+
+pub type WideHalfBandDownsampler<T> = WideHalfBandDownsamplerN<T, MAX_POLYPHASE_COEFFICIENTS>;
+
+impl<T: WIDE, const N: usize> WideHalfBandDownsamplerN<T, N> {
+    pub fn new(coefficients: WidePolyphaseCoefficientsN<T, N>) -> Self {
+        WideHalfBandDownsamplerN {
+            sections: WidePolyphaseSections::new(),
+            coeffs: coefficients,
+        }
+    }
+
+    /// Takes two input samples in time order. The output is aligned to the second.
+    pub fn process(&mut self, input: [T; 2]) -> T {
+        let a = self.sections.process_chain_z1(&self.coeffs, 0, input[1]);
+        let b = self.sections.process_chain_z1(&self.coeffs, 1, input[0]);
+        (a + b) * T::N0_5
+    }
+
+    /// Clears the filter memory
+    pub fn reset(&mut self) {
+        self.sections = WidePolyphaseSections::new();
+    }
+
+    pub fn state(&self) -> WideHalfBandStateN<T, N> {
+        WideHalfBandStateN {
+            sections: self.sections,
+        }
+    }
+
+    pub fn set_state(&mut self, state: &WideHalfBandStateN<T, N>) {
+        self.sections = state.sections;
+    }
+}
+
+/// `WIDE` version of `OversamplingCoefficientsN`, with the same design in every lane. The
+/// latency is that of the scalar coefficients.
+#[derive(Copy, Clone, Debug)]
+pub struct WideOversamplingCoefficientsN<T: WIDE, const N: usize> {
+    pub stages: [WidePolyphaseCoefficientsN<T, N>; MAX_OVERSAMPLING_STAGES],
+    pub stage_count: usize,
+}
+
+pub type WideOversamplingCoefficients<T> =
+    WideOversamplingCoefficientsN<T, MAX_POLYPHASE_COEFFICIENTS>;
+
+impl<T: WIDE, const N: usize> WideOversamplingCoefficientsN<T, N> {
+    pub fn from<A: FP>(
+        coeffs: &OversamplingCoefficientsN<A, N>,
+    ) -> WideOversamplingCoefficientsN<T, N> {
+        WideOversamplingCoefficientsN {
+            stages: coeffs.stages.map(WidePolyphaseCoefficientsN::from),
+            stage_count: coeffs.stage_count,
+        }
+    }
+
+    pub fn factor(&self) -> usize {
+        1 << self.stage_count
+    }
+}
+
+/// Snapshot of the internal state of a `WideUpsampler` or `WideDownsampler`
+#[derive(Copy, Clone, Debug)]
+pub struct WideResamplerStateN<T: WIDE, const N: usize> {
+    stages: [WideHalfBandStateN<T, N>; MAX_OVERSAMPLING_STAGES],
+}
+
+pub type WideResamplerState<T> = WideResamplerStateN<T, MAX_POLYPHASE_COEFFICIENTS>;
+
+#[derive(Copy, Clone, Debug)]
+pub struct WideUpsamplerN<T: WIDE, const N: usize> {
+    stages: [WideHalfBandUpsamplerN<T, N>; MAX_OVERSAMPLING_STAGES],
+    stage_count: usize,
+}
+
+pub type WideUpsampler<T> = WideUpsamplerN<T, MAX_POLYPHASE_COEFFICIENTS>;
+
+impl<T: WIDE, const N: usize> WideUpsamplerN<T, N> {
+    pub fn new(coeffs: &WideOversamplingCoefficientsN<T, N>) -> Self {
+        WideUpsamplerN {
+            stages: coeffs.stages.map(WideHalfBandUpsamplerN::new),
+            stage_count: coeffs.stage_count,
+        }
+    }
+
+    /// Fills `output`, which must hold exactly one sample per step of the factor
+    pub fn process(&mut self, input_sample: T, output: &mut [T]) {
+        assert_eq!(output.len(), 1 << self.stage_count);
+        output[0] = input_sample;
+        let mut length = 1;
+        for stage in self.stages[..self.stage_count].iter_mut() {
+            let mut expanded = [T::ZERO; 1 << MAX_OVERSAMPLING_STAGES];
+            for i in 0..length {
+                let [a, b] = stage.process(output[i]);
+                expanded[2 * i] = a;
+                expanded[2 * i + 1] = b;
+            }
+            length *= 2;
+            output[..length].copy_from_slice(&expanded[..length]);
+        }
+    }
+
+    /// Clears the filter memory
+    pub fn reset(&mut self) {
+        for stage in self.stages.iter_mut() {
+            stage.reset();
+        }
+    }
+
+    pub fn state(&self) -> WideResamplerStateN<T, N> {
+        WideResamplerStateN {
+            stages: self.stages.map(|stage| stage.state()),
+        }
+    }
+
+    pub fn set_state(&mut self, state: &WideResamplerStateN<T, N>) {
+        for (stage, state) in self.stages.iter_mut().zip(state.stages.iter()) {
+            stage.set_state(state);
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct WideDownsamplerN<T: WIDE, const N: usize> {
+    stages: [WideHalfBandDownsamplerN<T, N>; MAX_OVERSAMPLING_STAGES],
+    stage_count: usize,
+}
+
+pub type WideDownsampler<T> = WideDownsamplerN<T, MAX_POLYPHASE_COEFFICIENTS>;
+
+impl<T: WIDE, const N: usize> WideDownsamplerN<T, N> {
+    pub fn new(coeffs: &WideOversamplingCoefficientsN<T, N>) -> Self {
+        WideDownsamplerN {
+            stages: coeffs.stages.map(WideHalfBandDownsamplerN::new),
+            stage_count: coeffs.stage_count,
+        }
+    }
+
+    /// Takes exactly one sample per step of the factor, in time order
+    pub fn process(&mut self, input: &[T]) -> T {
+        assert_eq!(input.len(), 1 << self.stage_count);
+        let mut buffer = [T::ZERO; 1 << MAX_OVERSAMPLING_STAGES];
+        buffer[..input.len()].copy_from_slice(input);
+        let mut length = input.len();
+        for stage in self.stages[..self.stage_count].iter_mut().rev() {
+            length /= 2;
+            for i in 0..length {
+                buffer[i] = stage.process([buffer[2 * i], buffer[2 * i + 1]]);
+            }
+        }
+        buffer[0]
+    }
+
+    /// Clears the filter memory
+    pub fn reset(&mut self) {
+        for stage in self.stages.iter_mut() {
+            stage.reset();
+        }
+    }
+
+    pub fn state(&self) -> WideResamplerStateN<T, N> {
+        WideResamplerStateN {
+            stages: self.stages.map(|stage| stage.state()),
+        }
+    }
+
+    pub fn set_state(&mut self, state: &WideResamplerStateN<T, N>) {
+        for (stage, state) in self.stages.iter_mut().zip(state.stages.iter()) {
+            stage.set_state(state);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wide::f32x4;
+
+    use crate::half_band::{Downsampler, OversamplingCoefficients, Upsampler};
+
+    use super::*;
+
+    fn rand(x: f32) -> f32 {
+        ((x * 12.9898).sin() * 43758.5453).fract()
+    }
+
+    #[test]
+    fn test_wide_oversampling() {
+        let coeffs = OversamplingCoefficients::new(8, 90.0f32, 0.05);
+        let wide_coeffs = WideOversamplingCoefficients::<f32x4>::from(&coeffs);
+        assert_eq!(wide_coeffs.factor(), 8);
+        let mut up = Upsampler::new(&coeffs);
+        let mut down = Downsampler::new(&coeffs);
+        let mut wide_up = WideUpsampler::new(&wide_coeffs);
+        let mut wide_down = WideDownsampler::new(&wide_coeffs);
+        let mut buffer = [0.0f32; 8];
+        let mut wide_buffer = [f32x4::ZERO; 8];
+        for i in 0..1000 {
+            let x = rand(i as f32);
+            up.process(x, &mut buffer);
+            wide_up.process(f32x4::from([x, -x, 0.0, x]), &mut wide_buffer);
+            for (y, wide_y) in buffer.iter().zip(wide_buffer.iter()) {
+                let lanes: [f32; 4] = (*wide_y).into();
+                assert!((lanes[0] - y).abs() < 1e-6);
+                assert!((lanes[1] + y).abs() < 1e-6);
+                assert_eq!(lanes[2], 0.0);
+            }
+            let y = down.process(&buffer);
+            let lanes: [f32; 4] = wide_down.process(&wide_buffer).into();
+            assert!((lanes[0] - y).abs() < 1e-6);
+            assert!((lanes[3] - y).abs() < 1e-6);
+        }
+
+        // Restoring a snapshot repeats the output
+        let (up_state, down_state) = (wide_up.state(), wide_down.state());
+        let mut round_trip = |up: &mut WideUpsampler<f32x4>, down: &mut WideDownsampler<f32x4>| {
+            (1000..1100)
+                .map(|i| {
+                    up.process(f32x4::splat(rand(i as f32)), &mut wide_buffer);
+                    let y: [f32; 4] = down.process(&wide_buffer).into();
+                    y
+                })
+                .collect::<Vec<_>>()
+        };
+        let first = round_trip(&mut wide_up, &mut wide_down);
+        wide_up.set_state(&up_state);
+        wide_down.set_state(&down_state);
+        assert_eq!(first, round_trip(&mut wide_up, &mut wide_down));
+    }
+}
@@ -0,0 +1,200 @@
+use num_complex::Complex;
+
+use crate::{
+    error::FilterDesignError,
+    polyphase_iir::{AllpassSections, PolyphaseCoefficientsN, MAX_POLYPHASE_COEFFICIENTS},
+    units::{ZSample, FP},
+};
+
+/// Coefficients for a 90 degree phase splitter made from the half-band design of
+/// `PolyphaseCoefficientsN`, shifted up by a quarter of the sample rate.
+///
+/// The two outputs are within `max_error_degrees()` of quadrature from `transition / 2` to
+/// `(1 - transition) / 2` times the sample rate. The error follows from the half-band
+/// attenuation as `2 * asin(10^(-dB / 20))`.
+#[derive(Copy, Clone, Debug)]
+pub struct HilbertCoefficientsN<T: FP, const N: usize> {
+    pub polyphase: PolyphaseCoefficientsN<T, N>,
+}
+
+pub type HilbertCoefficients<T> = HilbertCoefficientsN<T, MAX_POLYPHASE_COEFFICIENTS>;
+
+impl<T: FP, const N: usize> HilbertCoefficientsN<T, N> {
+    /// Responses of the in-phase and quadrature outputs
+    pub fn get_bode_samples(&self, z: ZSample<T>) -> (Complex<T>, Complex<T>) {
+        //Use y.norm() for amplitude and y.arg().to_degrees() for phase. Add to combine phase.
+
+        let (a, b) = self.polyphase.path_bode_samples(z, true);
+        (a, b * z.pow1)
+    }
+
+    /// Largest deviation from 90 degrees between the outputs inside the band
+    pub fn max_error_degrees(&self) -> T {
+        let error = T::N10.powf(-self.polyphase.attenuation_db() / 20.0f32.into());
+        (T::N2 * error.asin()).to_degrees()
+    }
+
+    /// Phase splitter in quadrature within `max_error_degrees` from `low_hz` to
+    /// `sample_rate_hz / 2 - low_hz`. Uses all `N` coefficients if that's not enough.
+    pub fn new(low_hz: T, max_error_degrees: T, sample_rate_hz: T) -> HilbertCoefficientsN<T, N> {
+        let error = (max_error_degrees.to_radians() * T::N0_5).sin();
+        let attenuation_db = -T::N20 * error.log10();
+        HilbertCoefficientsN {
+            polyphase: PolyphaseCoefficientsN::design(
+                attenuation_db,
+                T::N2 * low_hz / sample_rate_hz,
+            ),
+        }
+    }
+
+    pub fn try_new(
+        low_hz: T,
+        max_error_degrees: T,
+        sample_rate_hz: T,
+    ) -> Result<HilbertCoefficientsN<T, N>, FilterDesignError> {
+        if !(low_hz.is_finite() && max_error_degrees.is_finite() && sample_rate_hz.is_finite()) {
+            return Err(FilterDesignError::NonFiniteParameter);
+        }
+        if sample_rate_hz <= T::N0 {
+            return Err(FilterDesignError::InvalidSampleRate);
+        }
+        if low_hz <= T::N0 || low_hz >= sample_rate_hz * T::N0_5 * T::N0_5 {
+            return Err(FilterDesignError::InvalidBandEdges);
+        }
+        let coeffs = HilbertCoefficientsN::new(low_hz, max_error_degrees, sample_rate_hz);
+        // Also catches bounds of zero or less, which no design reaches
+        if coeffs.max_error_degrees() > max_error_degrees {
+            return Err(FilterDesignError::InvalidOrder);
+        }
+        Ok(coeffs)
+    }
+}
+
+/// Snapshot of the internal state of a `Hilbert`
+#[derive(Copy, Clone, Debug)]
+pub struct HilbertStateN<T: FP, const N: usize> {
+    sections: AllpassSections<T, N>,
+    previous_input: T,
+}
+
+pub type HilbertState<T> = HilbertStateN<T, MAX_POLYPHASE_COEFFICIENTS>;
+
+/// Turns a real signal into an analytic one, with the input in the real part and its
+/// quadrature, 90 degrees behind for positive frequencies, in the imaginary part. Both are
+/// delayed and phase shifted by the same allpass response.
+#[derive(Copy, Clone, Debug)]
+pub struct HilbertN<T: FP, const N: usize> {
+    sections: AllpassSections<T, N>,
+    previous_input: T,
+    pub coeffs: HilbertCoefficientsN<T, N>,
+}
+
+pub type Hilbert<T> = HilbertN<T, MAX_POLYPHASE_COEFFICIENTS>;
+
+impl<T: FP, const N: usize> HilbertN<T, N> {
+    pub fn new(coefficients: HilbertCoefficientsN<T, N>) -> Self {
+        HilbertN {
+            sections: AllpassSections::new(),
+            previous_input: T::N0,
+            coeffs: coefficients,
+        }
+    }
+
+    /// The magnitude of the result follows the envelope of the input
+    pub fn process(&mut self, input_sample: T) -> Complex<T> {
+        let polyphase = &self.coeffs.polyphase;
+        let in_phase = self
+            .sections
+            .process_chain(polyphase, 0, true, input_sample);
+        let quadrature = self
+            .sections
+            .process_chain(polyphase, 1, true, self.previous_input);
+        self.previous_input = input_sample;
+        Complex::new(in_phase, quadrature)
+    }
+
+    pub fn update_coefficients(&mut self, new_coefficients: HilbertCoefficientsN<T, N>) {
+        self.coeffs = new_coefficients;
+    }
+
+    /// Clears the filter memory
+    pub fn reset(&mut self) {
+        self.sections.reset();
+        self.previous_input = T::N0;
+    }
+
+    pub fn state(&self) -> HilbertStateN<T, N> {
+        HilbertStateN {
+            sections: self.sections,
+            previous_input: self.previous_input,
+        }
+    }
+
+    pub fn set_state(&mut self, state: &HilbertStateN<T, N>) {
+        self.sections = state.sections;
+        self.previous_input = state.previous_input;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quadrature_error() {
+        let fs = 48000.0f64;
+        for (low, max_error) in [(50.0, 1.0), (20.0, 0.1), (50.0, 0.01), (500.0, 0.003)].iter() {
+            let coeffs = HilbertCoefficients::new(*low, *max_error, fs);
+            assert!(coeffs.max_error_degrees() <= *max_error);
+            for i in 0..=1000 {
+                let f = low + (fs * 0.5 - 2.0 * low) * i as f64 / 1000.0;
+                let (in_phase, quadrature) = coeffs.get_bode_samples(ZSample::new(f, fs));
+                assert!((in_phase.norm() - 1.0).abs() < 1e-9);
+                assert!((quadrature.norm() - 1.0).abs() < 1e-9);
+                // The quadrature output lags by 90 degrees
+                let difference = (quadrature / in_phase).arg().to_degrees();
+                assert!((difference + 90.0).abs() <= *max_error);
+            }
+        }
+        assert_eq!(
+            HilbertCoefficients::try_new(1.0f64, 0.001, fs).unwrap_err(),
+            FilterDesignError::InvalidOrder
+        );
+        assert_eq!(
+            HilbertCoefficients::try_new(20000.0f64, 1.0, fs).unwrap_err(),
+            FilterDesignError::InvalidBandEdges
+        );
+        assert_eq!(
+            HilbertCoefficients::try_new(50.0f64, 0.0, fs).unwrap_err(),
+            FilterDesignError::InvalidOrder
+        );
+    }
+
+    #[test]
+    fn test_envelope() {
+        let fs = 48000.0f64;
+        let coeffs = HilbertCoefficients::new(20.0, 0.1, fs);
+        let mut filter = Hilbert::new(coeffs);
+        for i in 0..48000 {
+            let x = 0.5 * (core::f64::consts::TAU * 1000.0 * i as f64 / fs).sin();
+            let y = filter.process(x);
+            if i > 4800 {
+                assert!((y.norm() - 0.5).abs() < 1e-3);
+            }
+        }
+    }
+
+    #[test]
+    fn test_state() {
+        let mut filter = Hilbert::new(HilbertCoefficients::new(20.0, 0.1, 48000.0f32));
+        let input = |i: usize| ((i as f32 * 12.9898).sin() * 43758.5453).fract();
+        for i in 0..500 {
+            filter.process(input(i));
+        }
+        let state = filter.state();
+        let first: Vec<Complex<f32>> = (500..1000).map(|i| filter.process(input(i))).collect();
+        filter.set_state(&state);
+        let second: Vec<Complex<f32>> = (500..1000).map(|i| filter.process(input(i))).collect();
+        assert_eq!(first, second);
+    }
+}
@@ -0,0 +1,243 @@
+use num_complex::Complex;
+
+use crate::{saturation::fast_tanh_ratio, units::FP};
+
+/// Coefficients for a four pole ladder lowpass, four TPT one-pole lowpass stages inside a
+/// zero-delay feedback loop.
+///
+/// The feedback lowers the passband gain to `1 / (1 + k)`, where `k = 4 * resonance`.
+#[derive(Copy, Clone, Debug)]
+pub struct LadderCoefficients<T: FP> {
+    pub g: T,
+    pub a1: T,
+    pub k: T,
+    /// Gain into the saturator at the input of the ladder, or 0 for a linear ladder
+    pub drive: T,
+}
+
+impl<T: FP> LadderCoefficients<T> {
+    pub fn get_bode_sample(self, z: Complex<T>) -> Complex<T> {
+        //Use y.norm() for amplitude and y.arg().to_degrees() for phase. Add to combine phase.
+        //Ignores the saturation.
+
+        let denominator = self.g + z * (self.g - T::N1) + T::N1;
+        let stage = (z + T::N1) * self.g / denominator;
+        let stages = stage * stage * stage * stage;
+
+        stages / (stages * self.k + T::N1)
+    }
+
+    /// A linear ladder. `resonance` goes from 0 to 1, where the filter self-oscillates with a
+    /// constant amplitude.
+    pub fn new(cutoff_hz: T, resonance: T, sample_rate_hz: T) -> LadderCoefficients<T> {
+        LadderCoefficients::driven(cutoff_hz, resonance, T::N0, sample_rate_hz)
+    }
+
+    /// A ladder with a saturator at its input, after the feedback is mixed in, computing
+    /// `tanh(drive * u) / drive`. Higher drives clip at lower levels, while small signals see the
+    /// linear ladder. The saturator lowers the loop gain of larger signals, so at a resonance of 1
+    /// ringing slowly dies out, and sustained self-oscillation needs a resonance above 1, where
+    /// the saturation keeps it bounded.
+    pub fn driven(
+        cutoff_hz: T,
+        resonance: T,
+        drive: T,
+        sample_rate_hz: T,
+    ) -> LadderCoefficients<T> {
+        let cutoff_hz = cutoff_hz.min(sample_rate_hz * T::N0_5);
+        let g = (T::PI() * cutoff_hz / sample_rate_hz).tan();
+        let a1 = g / (T::N1 + g);
+        let k = T::N4 * resonance.max(T::N0);
+        LadderCoefficients {
+            g,
+            a1,
+            k,
+            drive: drive.max(T::N0),
+        }
+    }
+}
+
+/// Snapshot of the internal state of a `Ladder`
+#[derive(Copy, Clone, Debug)]
+pub struct LadderState<T: FP> {
+    ic1eq: [T; 4],
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Ladder<T: FP> {
+    ic1eq: [T; 4],
+    pub coeffs: LadderCoefficients<T>,
+}
+
+impl<T: FP> Ladder<T> {
+    pub fn new(coefficients: LadderCoefficients<T>) -> Self {
+        Ladder {
+            ic1eq: [T::N0; 4],
+            coeffs: coefficients,
+        }
+    }
+
+    pub fn process(&mut self, input_sample: T) -> T {
+        let a1 = self.coeffs.a1;
+        // Each stage outputs a1 * input + (1 - a1) * ic1eq, so the ladder output is
+        // a1^4 * u + sigma with u = gain * (input - k * output), solved for the output
+        let b = T::N1 - a1;
+        let mut sigma = T::N0;
+        for ic1eq in self.ic1eq.iter() {
+            sigma = sigma * a1 + b * *ic1eq;
+        }
+        let a1_pow4 = a1 * a1 * a1 * a1;
+        let k = self.coeffs.k;
+
+        // The saturator is replaced by its gain tanh(x) / x at the input the linear ladder
+        // would give it, which keeps the feedback loop solvable without iterating
+        let mut gain = T::N1;
+        if self.coeffs.drive > T::N0 {
+            let output = (a1_pow4 * input_sample + sigma) / (T::N1 + k * a1_pow4);
+            gain = fast_tanh_ratio((input_sample - k * output) * self.coeffs.drive);
+        }
+        let output = (a1_pow4 * gain * input_sample + sigma) / (T::N1 + k * a1_pow4 * gain);
+
+        let mut u = gain * (input_sample - k * output);
+        for ic1eq in self.ic1eq.iter_mut() {
+            let v1 = a1 * (u - *ic1eq);
+            let v2 = v1 + *ic1eq;
+            *ic1eq = v2 + v1;
+            u = v2;
+        }
+        u
+    }
+
+    pub fn update_coefficients(&mut self, new_coefficients: LadderCoefficients<T>) {
+        self.coeffs = new_coefficients;
+    }
+
+    /// Clears the filter memory
+    pub fn reset(&mut self) {
+        self.ic1eq = [T::N0; 4];
+    }
+
+    pub fn state(&self) -> LadderState<T> {
+        LadderState { ic1eq: self.ic1eq }
+    }
+
+    pub fn set_state(&mut self, state: LadderState<T>) {
+        self.ic1eq = state.ic1eq;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        first_order_iir::{IIR1Coefficients, IIR1},
+        units::ZSample,
+    };
+
+    use super::*;
+
+    fn rand(x: f32) -> f32 {
+        ((x * 12.9898).sin() * 43758.5453).fract()
+    }
+
+    #[test]
+    fn test_ladder_without_resonance() {
+        let fs = 48000.0;
+        let mut ladder = Ladder::new(LadderCoefficients::new(1000.0, 0.0, fs));
+        let mut stages = [IIR1::new(IIR1Coefficients::lowpass(1000.0, 0.0, fs)); 4];
+        for i in 0..1000 {
+            let x = rand(i as f32);
+            let expected = stages.iter_mut().fold(x, |x, stage| stage.process(x));
+            assert!((ladder.process(x) - expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_ladder_resonance() {
+        let fs = 48000.0f64;
+        let coeffs = LadderCoefficients::new(1000.0, 0.5, fs);
+        let dc = coeffs.get_bode_sample(ZSample::new(0.0, fs).pow1).norm();
+        assert!((dc - 1.0 / 3.0).abs() < 1e-12);
+        let peak = coeffs.get_bode_sample(ZSample::new(1000.0, fs).pow1).norm();
+        assert!(peak > dc);
+
+        // The impulse response of the linear ladder matches its bode plot
+        let mut ladder = Ladder::new(coeffs);
+        let response: Vec<f64> = (0..4000)
+            .map(|i| ladder.process(if i == 0 { 1.0 } else { 0.0 }))
+            .collect();
+        let z = ZSample::new(1000.0, fs).pow1;
+        let mut sum = Complex::new(0.0, 0.0);
+        let mut z_pow = Complex::new(1.0, 0.0);
+        for y in response.iter() {
+            sum += z_pow * y;
+            z_pow *= z;
+        }
+        assert!((sum - coeffs.get_bode_sample(z)).norm() < 1e-9);
+
+        // At full resonance the linear ladder rings forever, while a driven one slowly dies out
+        let mut ladder = Ladder::new(LadderCoefficients::new(1000.0, 1.0, fs));
+        ladder.process(1.0);
+        let tail: Vec<f64> = (0..96000).map(|_| ladder.process(0.0)).collect();
+        let early_peak = tail[1000..2000].iter().fold(0.0f64, |m, y| m.max(y.abs()));
+        let late_peak = tail[88000..].iter().fold(0.0f64, |m, y| m.max(y.abs()));
+        assert!((late_peak / early_peak - 1.0).abs() < 1e-9);
+        let mut ladder = Ladder::new(LadderCoefficients::driven(1000.0, 1.0, 2.0, fs));
+        ladder.process(1.0);
+        let tail: Vec<f64> = (0..96000).map(|_| ladder.process(0.0)).collect();
+        let early_peak = tail[1000..2000].iter().fold(0.0f64, |m, y| m.max(y.abs()));
+        let late_peak = tail[88000..].iter().fold(0.0f64, |m, y| m.max(y.abs()));
+        assert!(late_peak < early_peak * 0.5 && late_peak > 0.0);
+
+        // Past full resonance a driven ladder keeps ringing without blowing up
+        let mut ladder = Ladder::new(LadderCoefficients::driven(1000.0, 1.1, 2.0, fs));
+        ladder.process(1.0);
+        let tail: Vec<f64> = (0..48000).map(|_| ladder.process(0.0)).collect();
+        let late_peak = tail[40000..].iter().fold(0.0f64, |m, y| m.max(y.abs()));
+        assert!(late_peak > 0.01 && late_peak < 2.0);
+    }
+
+    fn amplitude(ladder: &mut Ladder<f64>, frequency_hz: f64, level: f64, fs: f64) -> f64 {
+        let squares: f64 = (0..19200)
+            .map(|i| {
+                let x = level * (core::f64::consts::TAU * frequency_hz * i as f64 / fs).sin();
+                ladder.process(x)
+            })
+            .skip(9600)
+            .map(|y| y * y)
+            .sum();
+        (squares / 9600.0 * 2.0).sqrt() / level
+    }
+
+    #[test]
+    fn test_driven_small_signal() {
+        let fs = 48000.0f64;
+        for resonance in [0.0, 0.5, 0.9].iter() {
+            let coeffs = LadderCoefficients::driven(1000.0, *resonance, 4.0, fs);
+            for f in [100.0, 1000.0, 3000.0].iter() {
+                let mut ladder = Ladder::new(coeffs);
+                let expected = coeffs.get_bode_sample(ZSample::new(*f, fs).pow1).norm();
+                assert!((amplitude(&mut ladder, *f, 1e-4, fs) / expected - 1.0).abs() < 1e-3);
+            }
+        }
+
+        // Loud signals are compressed
+        let coeffs = LadderCoefficients::driven(1000.0, 0.0, 4.0, fs);
+        let mut ladder = Ladder::new(coeffs);
+        assert!(amplitude(&mut ladder, 100.0, 1.0, fs) < 0.5);
+    }
+
+    #[test]
+    fn test_driven_rings_out_below_full_resonance() {
+        let fs = 48000.0f64;
+        for resonance in [0.3, 0.9, 0.99].iter() {
+            for level in [1e-6, 1.0, 10.0].iter() {
+                let mut ladder =
+                    Ladder::new(LadderCoefficients::driven(1000.0, *resonance, 4.0, fs));
+                ladder.process(*level);
+                let tail: Vec<f64> = (0..48000).map(|_| ladder.process(0.0)).collect();
+                let late_peak = tail[40000..].iter().fold(0.0f64, |m, y| m.max(y.abs()));
+                assert!(late_peak < 1e-6 * level);
+            }
+        }
+    }
+}
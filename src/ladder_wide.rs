@@ -0,0 +1,182 @@
+use crate::{
+    ladder::LadderCoefficients,
+    saturation::fast_tanh_ratio_w,
+    units::FP,
+    wide_units::{fast_prewarp_w, WIDE},
+};
+
+#[derive(Copy, Clone, Debug)]
+pub struct WideLadderCoefficients<T: WIDE> {
+    pub g: T,
+    pub a1: T,
+    pub k: T,
+    /// Gain into the saturator at the input of the ladder, or 0 for a linear ladder
+    pub drive: T,
+}
+
+impl<T: WIDE> WideLadderCoefficients<T> {
+    pub fn from<A: FP>(coeffs: LadderCoefficients<A>) -> WideLadderCoefficients<T> {
+        WideLadderCoefficients {
+            g: T::from_w(coeffs.g),
+            a1: T::from_w(coeffs.a1),
+            k: T::from_w(coeffs.k),
+            drive: T::from_w(coeffs.drive),
+        }
+    }
+
+    /// `WIDE` version of `LadderCoefficients::driven` using `fast_prewarp_w`, with separate
+    /// parameters for each lane. Lanes with a drive of 0 stay linear.
+    pub fn fast(
+        cutoff_hz: T,
+        resonance: T,
+        drive: T,
+        sample_rate_hz: T,
+    ) -> WideLadderCoefficients<T> {
+        let g = fast_prewarp_w(cutoff_hz, sample_rate_hz);
+        let a1 = g / (T::N1 + g);
+        let k = T::N4 * resonance.max(T::N0);
+        WideLadderCoefficients {
+            g,
+            a1,
+            k,
+            drive: drive.max(T::N0),
+        }
+    }
+}
+
+/// Snapshot of the internal state of a `WideLadder`
+#[derive(Copy, Clone, Debug)]
+pub struct WideLadderState<T: WIDE> {
+    ic1eq: [T; 4],
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct WideLadder<T: WIDE> {
+    ic1eq: [T; 4],
+    pub coeffs: WideLadderCoefficients<T>,
+}
+
+impl<T: WIDE> WideLadder<T> {
+    pub fn new(coefficients: WideLadderCoefficients<T>) -> Self {
+        WideLadder {
+            ic1eq: [T::ZERO; 4],
+            coeffs: coefficients,
+        }
+    }
+
+    pub fn process(&mut self, input_sample: T) -> T {
+        let a1 = self.coeffs.a1;
+        let b = T::N1 - a1;
+        let mut sigma = T::ZERO;
+        for ic1eq in self.ic1eq.iter() {
+            sigma = sigma * a1 + b * *ic1eq;
+        }
+        let a1_pow4 = a1 * a1 * a1 * a1;
+        let k = self.coeffs.k;
+
+        // Lanes can differ in drive, so the saturator always runs. Its gain is exactly 1 at a
+        // drive of 0, which leaves those lanes linear.
+        let output = (a1_pow4 * input_sample + sigma) / (T::N1 + k * a1_pow4);
+        let gain = fast_tanh_ratio_w((input_sample - k * output) * self.coeffs.drive);
+        let output = (a1_pow4 * gain * input_sample + sigma) / (T::N1 + k * a1_pow4 * gain);
+
+        let mut u = gain * (input_sample - k * output);
+        for ic1eq in self.ic1eq.iter_mut() {
+            let v1 = a1 * (u - *ic1eq);
+            let v2 = v1 + *ic1eq;
+            *ic1eq = v2 + v1;
+            u = v2;
+        }
+        u
+    }
+
+    pub fn update_coefficients(&mut self, new_coefficients: WideLadderCoefficients<T>) {
+        self.coeffs = new_coefficients;
+    }
+
+    /// Clears the filter memory
+    pub fn reset(&mut self) {
+        self.ic1eq = [T::ZERO; 4];
+    }
+
+    pub fn state(&self) -> WideLadderState<T> {
+        WideLadderState { ic1eq: self.ic1eq }
+    }
+
+    pub fn set_state(&mut self, state: WideLadderState<T>) {
+        self.ic1eq = state.ic1eq;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wide::f64x2;
+
+    use crate::ladder::Ladder;
+
+    use super::*;
+
+    fn rand(x: f64) -> f64 {
+        ((x * 12.9898).sin() * 43758.5453).fract()
+    }
+
+    #[test]
+    fn test_wide_ladder() {
+        let fs = 48000.0;
+        for drive in [0.0, 3.0].iter() {
+            let coeffs = LadderCoefficients::driven(2000.0, 0.8, *drive, fs);
+            let mut ladder = Ladder::new(coeffs);
+            let mut wide_ladder = WideLadder::<f64x2>::new(WideLadderCoefficients::from(coeffs));
+            for i in 0..1000 {
+                let x = rand(i as f64);
+                let y = ladder.process(x);
+                let wide_y: [f64; 2] = wide_ladder.process(f64x2::splat(x)).into();
+                assert!((wide_y[0] - y).abs() < 1e-12);
+            }
+        }
+
+        let fast = WideLadderCoefficients::<f64x2>::fast(
+            f64x2::from([1000.0, 4000.0]),
+            f64x2::splat(0.5),
+            f64x2::splat(0.0),
+            f64x2::splat(fs),
+        );
+        let a1: [f64; 2] = fast.a1.into();
+        assert!((a1[1] / LadderCoefficients::new(4000.0, 0.5, fs).a1 - 1.0).abs() < 1e-5);
+
+        // A lane with a drive of 0 stays linear next to a driven one
+        let fast = WideLadderCoefficients::<f64x2>::fast(
+            f64x2::splat(1000.0),
+            f64x2::splat(0.5),
+            f64x2::from([0.0, 4.0]),
+            f64x2::splat(fs),
+        );
+        let drive: [f64; 2] = fast.drive.into();
+        assert_eq!(drive, [0.0, 4.0]);
+        let mut linear = WideLadder::new(WideLadderCoefficients::<f64x2>::fast(
+            f64x2::splat(1000.0),
+            f64x2::splat(0.5),
+            f64x2::splat(0.0),
+            f64x2::splat(fs),
+        ));
+        let mut wide_ladder = WideLadder::new(fast);
+        for i in 0..1000 {
+            let x = f64x2::splat(rand(i as f64));
+            let y: [f64; 2] = linear.process(x).into();
+            let wide_y: [f64; 2] = wide_ladder.process(x).into();
+            assert_eq!(wide_y[0], y[0]);
+        }
+
+        // Drive set on linear coefficients takes effect
+        let mut coeffs =
+            WideLadderCoefficients::<f64x2>::from(LadderCoefficients::new(2000.0, 0.8, fs));
+        coeffs.drive = f64x2::splat(3.0);
+        let mut wide_ladder = WideLadder::new(coeffs);
+        let mut ladder = Ladder::new(LadderCoefficients::driven(2000.0, 0.8, 3.0, fs));
+        for i in 0..1000 {
+            let x = rand(i as f64);
+            let wide_y: [f64; 2] = wide_ladder.process(f64x2::splat(x)).into();
+            assert!((wide_y[0] - ladder.process(x)).abs() < 1e-12);
+        }
+    }
+}
@@ -2,21 +2,43 @@
 #![feature(test)]
 
 pub mod const_butterworth;
+pub mod error;
+pub mod saturation;
 pub mod units;
 pub mod wide_units;
 
 pub mod linkwitz_riley;
 pub mod linkwitz_riley_wide;
 
+pub mod comb;
+pub mod dc_blocker;
 pub mod filter_band;
 pub mod first_order_iir;
+pub mod fractional_order;
+pub mod half_band;
+pub mod hilbert;
+pub mod ladder;
+pub mod nonlinear_second_order_iir;
+pub mod oversampled;
+pub mod polyphase_iir;
+pub mod sallen_key;
 pub mod second_order_iir;
+pub mod smoothed_filter_band;
+pub mod smoother;
+pub mod thiran;
 
+pub mod dc_blocker_wide;
 pub mod filter_band_wide;
 pub mod first_order_iir_wide;
+pub mod half_band_wide;
+pub mod ladder_wide;
 pub mod second_order_iir_wide;
+pub mod smoother_wide;
 
-const MAX_CASCADE_COUNT: usize = 32;
+pub mod multichannel_wide;
+
+/// Number of second order stages in the default `FilterBand` and related types
+pub const MAX_CASCADE_COUNT: usize = 32;
 
 #[cfg(test)]
 mod tests {
@@ -1,40 +1,54 @@
 use num_complex::Complex;
 
 use crate::{
-    filter_band::{FilterBand, FilterBandCoefficients, ProcessType},
+    filter_band::{FilterBandCoefficientsN, FilterBandN, FilterBandStateN, ProcessType},
     units::{ZSample, FP},
+    MAX_CASCADE_COUNT,
 };
 
 #[derive(Copy, Clone, Debug)]
-pub struct LinkwitzRileyCoefficients<T: FP> {
-    pub coeffs: FilterBandCoefficients<T>,
+pub struct LinkwitzRileyCoefficientsN<T: FP, const N: usize> {
+    pub coeffs: FilterBandCoefficientsN<T, N>,
 }
 
-impl<T: FP> LinkwitzRileyCoefficients<T> {
+pub type LinkwitzRileyCoefficients<T> = LinkwitzRileyCoefficientsN<T, MAX_CASCADE_COUNT>;
+
+impl<T: FP, const N: usize> LinkwitzRileyCoefficientsN<T, N> {
     pub fn get_bode_sample(&self, z: ZSample<T>) -> Complex<T> {
         //Use y.norm() for amplitude and y.arg().to_degrees() for phase. Add to combine phase.
         self.coeffs.get_bode_sample(z) * self.coeffs.get_bode_sample(z)
     }
 
     //The resulting Linkwitz-Riley filter will have 2x to order of the input coefficients and 2x gain
-    pub fn from(coeffs: FilterBandCoefficients<T>) -> Self {
-        LinkwitzRileyCoefficients { coeffs }
+    pub fn from(coeffs: FilterBandCoefficientsN<T, N>) -> Self {
+        LinkwitzRileyCoefficientsN { coeffs }
     }
 }
 
+/// Snapshot of the internal state of a `LinkwitzRileyBand`
+#[derive(Copy, Clone, Debug)]
+pub struct LinkwitzRileyBandStateN<T: FP, const N: usize> {
+    filter1: FilterBandStateN<T, N>,
+    filter2: FilterBandStateN<T, N>,
+}
+
+pub type LinkwitzRileyBandState<T> = LinkwitzRileyBandStateN<T, MAX_CASCADE_COUNT>;
+
 #[derive(Copy, Clone)]
-pub struct LinkwitzRileyBand<T: FP> {
-    pub filter1: FilterBand<T>,
-    pub filter2: FilterBand<T>,
+pub struct LinkwitzRileyBandN<T: FP, const N: usize> {
+    pub filter1: FilterBandN<T, N>,
+    pub filter2: FilterBandN<T, N>,
     pub process: fn(&mut Self, T) -> T,
 }
 
-impl<T: FP> LinkwitzRileyBand<T> {
-    pub fn from(lw_coeffs: &LinkwitzRileyCoefficients<T>) -> LinkwitzRileyBand<T> {
-        LinkwitzRileyBand {
-            filter1: FilterBand::from(&lw_coeffs.coeffs),
-            filter2: FilterBand::from(&lw_coeffs.coeffs),
-            process: LinkwitzRileyBand::get_process(lw_coeffs.coeffs.process),
+pub type LinkwitzRileyBand<T> = LinkwitzRileyBandN<T, MAX_CASCADE_COUNT>;
+
+impl<T: FP, const N: usize> LinkwitzRileyBandN<T, N> {
+    pub fn from(lw_coeffs: &LinkwitzRileyCoefficientsN<T, N>) -> LinkwitzRileyBandN<T, N> {
+        LinkwitzRileyBandN {
+            filter1: FilterBandN::from(&lw_coeffs.coeffs),
+            filter2: FilterBandN::from(&lw_coeffs.coeffs),
+            process: LinkwitzRileyBandN::get_process(lw_coeffs.coeffs.process),
         }
     }
 
@@ -60,22 +74,55 @@ impl<T: FP> LinkwitzRileyBand<T> {
 
     pub fn get_process(process_type: ProcessType) -> fn(&mut Self, T) -> T {
         match process_type {
-            ProcessType::ProcessIIR1Only => LinkwitzRileyBand::process_iir1_only,
-            ProcessType::ProcessIIR2Only => LinkwitzRileyBand::process_iir2_only,
-            ProcessType::ProcessEvenOrderCascade => LinkwitzRileyBand::process_even_order_cascade,
-            ProcessType::ProcessOddOrderCascade => LinkwitzRileyBand::process_odd_order_cascade,
+            ProcessType::ProcessIIR1Only => LinkwitzRileyBandN::process_iir1_only,
+            ProcessType::ProcessIIR2Only => LinkwitzRileyBandN::process_iir2_only,
+            ProcessType::ProcessEvenOrderCascade => LinkwitzRileyBandN::process_even_order_cascade,
+            ProcessType::ProcessOddOrderCascade => LinkwitzRileyBandN::process_odd_order_cascade,
         }
     }
 
-    pub fn update(&mut self, lw_coeffs: &LinkwitzRileyCoefficients<T>) {
+    pub fn update(&mut self, lw_coeffs: &LinkwitzRileyCoefficientsN<T, N>) {
         self.filter1.update(&lw_coeffs.coeffs);
         self.filter2.update(&lw_coeffs.coeffs);
-        self.process = LinkwitzRileyBand::get_process(lw_coeffs.coeffs.process);
+        self.process = LinkwitzRileyBandN::get_process(lw_coeffs.coeffs.process);
+    }
+
+    /// Clears the memory of both filters
+    pub fn reset(&mut self) {
+        self.filter1.reset();
+        self.filter2.reset();
+    }
+
+    /// Sets the memory of both filters to the steady state for a constant input of `value`.
+    /// Same as `prime` without the returned output.
+    pub fn reset_to(&mut self, value: T) {
+        self.prime(value);
+    }
+
+    /// Sets the memory of both filters to the steady state for a constant input of
+    /// `input_value`. Returns the steady state output.
+    pub fn prime(&mut self, input_value: T) -> T {
+        let value = self.filter1.prime(input_value);
+        self.filter2.prime(value)
+    }
+
+    pub fn state(&self) -> LinkwitzRileyBandStateN<T, N> {
+        LinkwitzRileyBandStateN {
+            filter1: self.filter1.state(),
+            filter2: self.filter2.state(),
+        }
+    }
+
+    pub fn set_state(&mut self, state: &LinkwitzRileyBandStateN<T, N>) {
+        self.filter1.set_state(&state.filter1);
+        self.filter2.set_state(&state.filter2);
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::{filter_band::FilterBandCoefficients, units::Units};
+
     use super::*;
 
     fn rand(x: f32) -> f32 {
@@ -106,4 +153,18 @@ mod tests {
 
         dbg!(left[500], right[500]);
     }
+
+    #[test]
+    fn test_reset_to() {
+        let coeffs = FilterBandCoefficients::lowshelf(1000.0, 6.0, 1.0, 3.0, 48000.0f32);
+        let mut filter = LinkwitzRileyBand::from(&LinkwitzRileyCoefficients::from(coeffs));
+        for i in 0..100 {
+            (filter.process)(&mut filter, rand(i as f32));
+        }
+        filter.reset_to(0.5);
+        let expected = 0.5 * 12.0f32.db_to_lin();
+        for _ in 0..1000 {
+            assert!(((filter.process)(&mut filter, 0.5) - expected).abs() < 1e-6);
+        }
+    }
 }
@@ -1,34 +1,48 @@
 use crate::{
     filter_band::ProcessType,
-    filter_band_wide::{WideFilterBand, WideFilterBandCoefficients},
+    filter_band_wide::{WideFilterBandCoefficientsN, WideFilterBandN, WideFilterBandStateN},
     wide_units::WIDE,
+    MAX_CASCADE_COUNT,
 };
 
 #[derive(Copy, Clone, Debug)]
-pub struct WideLinkwitzRileyCoefficients<T: WIDE> {
-    pub coeffs: WideFilterBandCoefficients<T>,
+pub struct WideLinkwitzRileyCoefficientsN<T: WIDE, const N: usize> {
+    pub coeffs: WideFilterBandCoefficientsN<T, N>,
 }
 
-impl<T: WIDE> WideLinkwitzRileyCoefficients<T> {
+pub type WideLinkwitzRileyCoefficients<T> = WideLinkwitzRileyCoefficientsN<T, MAX_CASCADE_COUNT>;
+
+impl<T: WIDE, const N: usize> WideLinkwitzRileyCoefficientsN<T, N> {
     //The resulting Linkwitz-Riley filter will have 2x to order of the input coefficients and 2x gain
-    pub fn from(coeffs: WideFilterBandCoefficients<T>) -> Self {
-        WideLinkwitzRileyCoefficients { coeffs }
+    pub fn from(coeffs: WideFilterBandCoefficientsN<T, N>) -> Self {
+        WideLinkwitzRileyCoefficientsN { coeffs }
     }
 }
 
+/// Snapshot of the internal state of a `WideLinkwitzRileyBand`
+#[derive(Copy, Clone, Debug)]
+pub struct WideLinkwitzRileyBandStateN<T: WIDE, const N: usize> {
+    filter1: WideFilterBandStateN<T, N>,
+    filter2: WideFilterBandStateN<T, N>,
+}
+
+pub type WideLinkwitzRileyBandState<T> = WideLinkwitzRileyBandStateN<T, MAX_CASCADE_COUNT>;
+
 #[derive(Copy, Clone)]
-pub struct WideLinkwitzRileyBand<T: WIDE> {
-    pub filter1: WideFilterBand<T>,
-    pub filter2: WideFilterBand<T>,
+pub struct WideLinkwitzRileyBandN<T: WIDE, const N: usize> {
+    pub filter1: WideFilterBandN<T, N>,
+    pub filter2: WideFilterBandN<T, N>,
     pub process: fn(&mut Self, T) -> T,
 }
 
-impl<T: WIDE> WideLinkwitzRileyBand<T> {
-    pub fn from(lw_coeffs: &WideLinkwitzRileyCoefficients<T>) -> WideLinkwitzRileyBand<T> {
-        WideLinkwitzRileyBand {
-            filter1: WideFilterBand::from(&lw_coeffs.coeffs),
-            filter2: WideFilterBand::from(&lw_coeffs.coeffs),
-            process: WideLinkwitzRileyBand::get_process(lw_coeffs.coeffs.process),
+pub type WideLinkwitzRileyBand<T> = WideLinkwitzRileyBandN<T, MAX_CASCADE_COUNT>;
+
+impl<T: WIDE, const N: usize> WideLinkwitzRileyBandN<T, N> {
+    pub fn from(lw_coeffs: &WideLinkwitzRileyCoefficientsN<T, N>) -> WideLinkwitzRileyBandN<T, N> {
+        WideLinkwitzRileyBandN {
+            filter1: WideFilterBandN::from(&lw_coeffs.coeffs),
+            filter2: WideFilterBandN::from(&lw_coeffs.coeffs),
+            process: WideLinkwitzRileyBandN::get_process(lw_coeffs.coeffs.process),
         }
     }
 
@@ -54,18 +68,51 @@ impl<T: WIDE> WideLinkwitzRileyBand<T> {
 
     pub fn get_process(process_type: ProcessType) -> fn(&mut Self, T) -> T {
         match process_type {
-            ProcessType::ProcessIIR1Only => WideLinkwitzRileyBand::process_iir1_only,
-            ProcessType::ProcessIIR2Only => WideLinkwitzRileyBand::process_iir2_only,
+            ProcessType::ProcessIIR1Only => WideLinkwitzRileyBandN::process_iir1_only,
+            ProcessType::ProcessIIR2Only => WideLinkwitzRileyBandN::process_iir2_only,
             ProcessType::ProcessEvenOrderCascade => {
-                WideLinkwitzRileyBand::process_even_order_cascade
+                WideLinkwitzRileyBandN::process_even_order_cascade
+            }
+            ProcessType::ProcessOddOrderCascade => {
+                WideLinkwitzRileyBandN::process_odd_order_cascade
             }
-            ProcessType::ProcessOddOrderCascade => WideLinkwitzRileyBand::process_odd_order_cascade,
         }
     }
 
-    pub fn update(&mut self, lw_coeffs: &WideLinkwitzRileyCoefficients<T>) {
+    pub fn update(&mut self, lw_coeffs: &WideLinkwitzRileyCoefficientsN<T, N>) {
         self.filter1.update(&lw_coeffs.coeffs);
         self.filter2.update(&lw_coeffs.coeffs);
-        self.process = WideLinkwitzRileyBand::get_process(lw_coeffs.coeffs.process);
+        self.process = WideLinkwitzRileyBandN::get_process(lw_coeffs.coeffs.process);
+    }
+
+    /// Clears the memory of both filters
+    pub fn reset(&mut self) {
+        self.filter1.reset();
+        self.filter2.reset();
+    }
+
+    /// Sets the memory of both filters to the steady state for a constant input of `value`.
+    /// Same as `prime` without the returned output.
+    pub fn reset_to(&mut self, value: T) {
+        self.prime(value);
+    }
+
+    /// Sets the memory of both filters to the steady state for a constant input of
+    /// `input_value`. Returns the steady state output.
+    pub fn prime(&mut self, input_value: T) -> T {
+        let value = self.filter1.prime(input_value);
+        self.filter2.prime(value)
+    }
+
+    pub fn state(&self) -> WideLinkwitzRileyBandStateN<T, N> {
+        WideLinkwitzRileyBandStateN {
+            filter1: self.filter1.state(),
+            filter2: self.filter2.state(),
+        }
+    }
+
+    pub fn set_state(&mut self, state: &WideLinkwitzRileyBandStateN<T, N>) {
+        self.filter1.set_state(&state.filter1);
+        self.filter2.set_state(&state.filter2);
     }
 }
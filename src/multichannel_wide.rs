@@ -0,0 +1,160 @@
+use num_traits::Zero;
+
+use crate::{filter_band_wide::WideFilterBandN, wide_units::WIDE};
+
+/// Processes a planar channel set in place, packing up to `T::LANES` channels into each vector.
+///
+/// `process` is called once per frame for each group of `T::LANES` channels, with the index of
+/// the group and the packed input. When the channel count is not a multiple of `T::LANES`, the
+/// unused lanes of the last group are fed zeros and their output is discarded. Only the frames
+/// shared by every channel are processed.
+pub fn process_planar<T: WIDE, F: FnMut(usize, T) -> T>(
+    channels: &mut [&mut [T::Element]],
+    mut process: F,
+) {
+    let frame_count = channels.iter().map(|c| c.len()).min().unwrap_or(0);
+    for (group_index, group) in channels.chunks_mut(T::LANES).enumerate() {
+        for frame in 0..frame_count {
+            let input = T::from_fn(|lane| group.get(lane).map_or(T::Element::zero(), |c| c[frame]));
+            process(group_index, input).for_each_lane(|lane, sample| {
+                if let Some(channel) = group.get_mut(lane) {
+                    channel[frame] = sample;
+                }
+            });
+        }
+    }
+}
+
+/// Processes an interleaved buffer of `channel_count` channels in place, packing up to
+/// `T::LANES` channels into each vector.
+///
+/// Channels are grouped and padded the same way as in `process_planar`. A trailing partial
+/// frame is left untouched.
+pub fn process_interleaved<T: WIDE, F: FnMut(usize, T) -> T>(
+    buffer: &mut [T::Element],
+    channel_count: usize,
+    mut process: F,
+) {
+    assert!(channel_count > 0);
+    for frame in buffer.chunks_exact_mut(channel_count) {
+        for (group_index, group) in frame.chunks_mut(T::LANES).enumerate() {
+            let input = T::from_fn(|lane| group.get(lane).copied().unwrap_or_else(Zero::zero));
+            process(group_index, input).for_each_lane(|lane, sample| {
+                if let Some(channel) = group.get_mut(lane) {
+                    *channel = sample;
+                }
+            });
+        }
+    }
+}
+
+impl<T: WIDE, const N: usize> WideFilterBandN<T, N> {
+    /// Processes up to `T::LANES` planar channels in place, one channel per lane
+    pub fn process_planar(&mut self, channels: &mut [&mut [T::Element]]) {
+        assert!(channels.len() <= T::LANES);
+        process_planar(channels, |_, input_sample| {
+            (self.process)(self, input_sample)
+        });
+    }
+
+    /// Processes an interleaved buffer of up to `T::LANES` channels in place
+    pub fn process_interleaved(&mut self, buffer: &mut [T::Element], channel_count: usize) {
+        assert!(channel_count <= T::LANES);
+        process_interleaved(buffer, channel_count, |_, input_sample| {
+            (self.process)(self, input_sample)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wide::{f32x4, f64x2};
+
+    use crate::{
+        filter_band::{FilterBand, FilterBandCoefficients},
+        filter_band_wide::{WideFilterBand, WideFilterBandCoefficients},
+    };
+
+    use super::*;
+
+    fn rand32(x: f32) -> f32 {
+        ((x * 12.989846024374758).sin() * 43758.545347294991945).fract()
+    }
+
+    fn rand64(x: f64) -> f64 {
+        ((x * 12.989846024374758).sin() * 43758.545347294991945).fract()
+    }
+
+    #[test]
+    fn test_planar_leftover_channels() {
+        let input: Vec<Vec<f32>> = (0..6)
+            .map(|ch| (0..1000).map(|x| rand32((x + ch * 1000) as f32)).collect())
+            .collect();
+
+        let coeffs = FilterBandCoefficients::highshelf(1000.0, 6.0, 1.0, 4.0, 48000.0);
+        let wide_coeffs = WideFilterBandCoefficients::<f32x4>::from(coeffs);
+
+        let mut output = input.clone();
+        let mut filters = [
+            WideFilterBand::from(&wide_coeffs),
+            WideFilterBand::from(&wide_coeffs),
+        ];
+        let mut channels: Vec<&mut [f32]> = output.iter_mut().map(|c| c.as_mut_slice()).collect();
+        process_planar(&mut channels, |group, input_sample| {
+            (filters[group].process)(&mut filters[group], input_sample)
+        });
+
+        for (input_channel, output_channel) in input.iter().zip(output.iter()) {
+            let mut filter = FilterBand::from(&coeffs);
+            for (x, y) in input_channel.iter().zip(output_channel.iter()) {
+                assert!(((filter.process)(&mut filter, *x) - y).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_interleaved_leftover_channels() {
+        let channel_count = 3;
+        let input: Vec<f64> = (0..3000).map(|x| rand64(x as f64)).collect();
+
+        let coeffs = FilterBandCoefficients::lowpass(1000.0, 1.0, 3.0, 48000.0);
+        let wide_coeffs = WideFilterBandCoefficients::<f64x2>::from(coeffs);
+
+        let mut output = input.clone();
+        let mut filters = [
+            WideFilterBand::from(&wide_coeffs),
+            WideFilterBand::from(&wide_coeffs),
+        ];
+        process_interleaved(&mut output, channel_count, |group, input_sample| {
+            (filters[group].process)(&mut filters[group], input_sample)
+        });
+
+        for ch in 0..channel_count {
+            let mut filter = FilterBand::from(&coeffs);
+            for (x, y) in input
+                .iter()
+                .skip(ch)
+                .step_by(channel_count)
+                .zip(output.iter().skip(ch).step_by(channel_count))
+            {
+                assert!(((filter.process)(&mut filter, *x) - y).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_filter_band_planar() {
+        let mut ch1: Vec<f32> = (0..1000).map(|x| rand32(x as f32)).collect();
+        let mut ch2: Vec<f32> = (1000..2000).map(|x| rand32(x as f32)).collect();
+        let expected = ch2.clone();
+
+        let coeffs = FilterBandCoefficients::bell(1000.0, 6.0, 1.0, 48000.0);
+        let mut filter = WideFilterBand::<f32x4>::from(&WideFilterBandCoefficients::from(coeffs));
+        filter.process_planar(&mut [&mut ch1, &mut ch2]);
+
+        let mut scalar_filter = FilterBand::from(&coeffs);
+        for (x, y) in expected.iter().zip(ch2.iter()) {
+            assert!(((scalar_filter.process)(&mut scalar_filter, *x) - y).abs() < 1e-6);
+        }
+    }
+}
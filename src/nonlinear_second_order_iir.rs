@@ -0,0 +1,134 @@
+use crate::{saturation::Saturation, second_order_iir::IIR2Coefficients, units::FP};
+
+/// Snapshot of the internal state of a `NonlinearIIR2`
+#[derive(Copy, Clone, Debug)]
+pub struct NonlinearIIR2State<T: FP> {
+    ic1eq: T,
+    ic2eq: T,
+}
+
+/// A SVF with a saturating bandpass integrator, for analog style behaviour at high resonance.
+///
+/// The integrator state is saturated once per sample before the usual zero-delay solve, so the
+/// filter costs about the same as `IIR2` and the cutoff tracks exactly as in the linear filter.
+/// `drive` scales the state into the saturation curve, so higher drives clip at lower levels.
+/// For small signals the output matches `IIR2`.
+#[derive(Copy, Clone, Debug)]
+pub struct NonlinearIIR2<T: FP> {
+    ic1eq: T,
+    ic2eq: T,
+    drive: T,
+    saturation: Saturation,
+    pub coeffs: IIR2Coefficients<T>,
+}
+
+impl<T: FP> NonlinearIIR2<T> {
+    pub fn new(coefficients: IIR2Coefficients<T>, drive: T, saturation: Saturation) -> Self {
+        NonlinearIIR2 {
+            ic1eq: T::N0,
+            ic2eq: T::N0,
+            drive: drive.max(T::epsilon()),
+            saturation,
+            coeffs: coefficients,
+        }
+    }
+
+    pub fn process(&mut self, input_sample: T) -> T {
+        let ic1eq = self.saturation.process(self.ic1eq * self.drive) / self.drive;
+        let v3 = input_sample - self.ic2eq;
+        let v1 = self.coeffs.a1 * ic1eq + self.coeffs.a2 * v3;
+        let v2 = self.ic2eq + self.coeffs.a2 * ic1eq + self.coeffs.a3 * v3;
+        self.ic1eq = T::N2 * v1 - ic1eq;
+        self.ic2eq = T::N2 * v2 - self.ic2eq;
+
+        self.coeffs.m0 * input_sample + self.coeffs.m1 * v1 + self.coeffs.m2 * v2
+    }
+
+    pub fn update_coefficients(&mut self, new_coefficients: IIR2Coefficients<T>) {
+        self.coeffs = new_coefficients;
+    }
+
+    pub fn set_drive(&mut self, drive: T) {
+        self.drive = drive.max(T::epsilon());
+    }
+
+    pub fn set_saturation(&mut self, saturation: Saturation) {
+        self.saturation = saturation;
+    }
+
+    /// Clears the filter memory
+    pub fn reset(&mut self) {
+        self.ic1eq = T::N0;
+        self.ic2eq = T::N0;
+    }
+
+    pub fn state(&self) -> NonlinearIIR2State<T> {
+        NonlinearIIR2State {
+            ic1eq: self.ic1eq,
+            ic2eq: self.ic2eq,
+        }
+    }
+
+    pub fn set_state(&mut self, state: NonlinearIIR2State<T>) {
+        self.ic1eq = state.ic1eq;
+        self.ic2eq = state.ic2eq;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::second_order_iir::IIR2;
+
+    use super::*;
+
+    fn rand(x: f64) -> f64 {
+        ((x * 12.9898).sin() * 43758.5453).fract()
+    }
+
+    #[test]
+    fn test_small_signals_are_linear() {
+        let coeffs = IIR2Coefficients::lowpass(1000.0, 0.0, 4.0, 48000.0);
+        let mut linear = IIR2::new(coeffs);
+        let mut nonlinear = NonlinearIIR2::new(coeffs, 1.0, Saturation::Tanh);
+        for i in 0..1000 {
+            let x = rand(i as f64) * 1e-4;
+            assert!((linear.process(x) - nonlinear.process(x)).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_high_resonance_is_bounded() {
+        let coeffs = IIR2Coefficients::bandpass(1000.0, 0.0, 200.0, 48000.0);
+        for saturation in [Saturation::Tanh, Saturation::Cubic, Saturation::Asymmetric].iter() {
+            let mut linear = IIR2::new(coeffs);
+            let mut nonlinear = NonlinearIIR2::new(coeffs, 1.0, *saturation);
+            let mut linear_peak = 0.0f64;
+            let mut nonlinear_peak = 0.0f64;
+            for i in 0..48000 {
+                let x = (i as f64 * core::f64::consts::TAU * 1000.0 / 48000.0).sin();
+                linear_peak = linear_peak.max(linear.process(x).abs());
+                nonlinear_peak = nonlinear_peak.max(nonlinear.process(x).abs());
+            }
+            assert!(linear_peak > 100.0);
+            assert!(nonlinear_peak < 10.0);
+        }
+    }
+
+    #[test]
+    fn test_state() {
+        let coeffs = IIR2Coefficients::bandpass(1000.0, 0.0, 20.0, 48000.0);
+        let mut filter = NonlinearIIR2::new(coeffs, 2.0, Saturation::Tanh);
+        for i in 0..500 {
+            filter.process(rand(i as f64));
+        }
+        let state = filter.state();
+        let first: Vec<f64> = (500..1000)
+            .map(|i| filter.process(rand(i as f64)))
+            .collect();
+        filter.set_state(state);
+        let second: Vec<f64> = (500..1000)
+            .map(|i| filter.process(rand(i as f64)))
+            .collect();
+        assert_eq!(first, second);
+    }
+}
@@ -0,0 +1,359 @@
+use crate::{
+    filter_band::{FilterBandN, FilterBandStateN},
+    first_order_iir::{IIR1State, IIR1},
+    half_band::{
+        DownsamplerN, OversamplingCoefficientsN, ResamplerStateN, UpsamplerN,
+        MAX_OVERSAMPLING_STAGES,
+    },
+    ladder::{Ladder, LadderState},
+    nonlinear_second_order_iir::{NonlinearIIR2, NonlinearIIR2State},
+    polyphase_iir::MAX_POLYPHASE_COEFFICIENTS,
+    sallen_key::{SallenKey, SallenKeyState},
+    second_order_iir::{IIR2State, IIR2},
+    units::FP,
+};
+
+/// A filter that turns one input sample into one output sample, so it can run inside an
+/// `OversampledN`
+pub trait Processor {
+    type Sample: FP;
+    /// Snapshot of the filter memory, as returned by the filter's own `state`
+    type State: Copy;
+    fn process(&mut self, input_sample: Self::Sample) -> Self::Sample;
+    fn reset(&mut self);
+    fn state(&self) -> Self::State;
+    fn set_state(&mut self, state: &Self::State);
+}
+
+impl<T: FP, const N: usize> Processor for FilterBandN<T, N> {
+    type Sample = T;
+    type State = FilterBandStateN<T, N>;
+    fn process(&mut self, input_sample: T) -> T {
+        (self.process)(self, input_sample)
+    }
+    fn reset(&mut self) {
+        FilterBandN::reset(self)
+    }
+    fn state(&self) -> FilterBandStateN<T, N> {
+        FilterBandN::state(self)
+    }
+    fn set_state(&mut self, state: &FilterBandStateN<T, N>) {
+        FilterBandN::set_state(self, state)
+    }
+}
+
+impl<T: FP> Processor for IIR1<T> {
+    type Sample = T;
+    type State = IIR1State<T>;
+    fn process(&mut self, input_sample: T) -> T {
+        IIR1::process(self, input_sample)
+    }
+    fn reset(&mut self) {
+        IIR1::reset(self)
+    }
+    fn state(&self) -> IIR1State<T> {
+        IIR1::state(self)
+    }
+    fn set_state(&mut self, state: &IIR1State<T>) {
+        IIR1::set_state(self, *state)
+    }
+}
+
+impl<T: FP> Processor for IIR2<T> {
+    type Sample = T;
+    type State = IIR2State<T>;
+    fn process(&mut self, input_sample: T) -> T {
+        IIR2::process(self, input_sample)
+    }
+    fn reset(&mut self) {
+        IIR2::reset(self)
+    }
+    fn state(&self) -> IIR2State<T> {
+        IIR2::state(self)
+    }
+    fn set_state(&mut self, state: &IIR2State<T>) {
+        IIR2::set_state(self, *state)
+    }
+}
+
+impl<T: FP> Processor for NonlinearIIR2<T> {
+    type Sample = T;
+    type State = NonlinearIIR2State<T>;
+    fn process(&mut self, input_sample: T) -> T {
+        NonlinearIIR2::process(self, input_sample)
+    }
+    fn reset(&mut self) {
+        NonlinearIIR2::reset(self)
+    }
+    fn state(&self) -> NonlinearIIR2State<T> {
+        NonlinearIIR2::state(self)
+    }
+    fn set_state(&mut self, state: &NonlinearIIR2State<T>) {
+        NonlinearIIR2::set_state(self, *state)
+    }
+}
+
+impl<T: FP> Processor for Ladder<T> {
+    type Sample = T;
+    type State = LadderState<T>;
+    fn process(&mut self, input_sample: T) -> T {
+        Ladder::process(self, input_sample)
+    }
+    fn reset(&mut self) {
+        Ladder::reset(self)
+    }
+    fn state(&self) -> LadderState<T> {
+        Ladder::state(self)
+    }
+    fn set_state(&mut self, state: &LadderState<T>) {
+        Ladder::set_state(self, *state)
+    }
+}
+
+impl<T: FP> Processor for SallenKey<T> {
+    type Sample = T;
+    type State = SallenKeyState<T>;
+    fn process(&mut self, input_sample: T) -> T {
+        SallenKey::process(self, input_sample)
+    }
+    fn reset(&mut self) {
+        SallenKey::reset(self)
+    }
+    fn state(&self) -> SallenKeyState<T> {
+        SallenKey::state(self)
+    }
+    fn set_state(&mut self, state: &SallenKeyState<T>) {
+        SallenKey::set_state(self, *state)
+    }
+}
+
+/// Snapshot of the internal state of an `OversampledN`, with the state `S` of its inner filter
+#[derive(Copy, Clone, Debug)]
+pub struct OversampledStateN<S, T: FP, const N: usize> {
+    inner: S,
+    upsampler: ResamplerStateN<T, N>,
+    downsampler: ResamplerStateN<T, N>,
+}
+
+pub type OversampledState<S, T> = OversampledStateN<S, T, MAX_POLYPHASE_COEFFICIENTS>;
+
+/// Runs a `Processor` at a multiple of the sample rate, between the half-band filters of an
+/// `OversamplingCoefficientsN`. Keeps resonant peaks near Nyquist from cramping and moves the
+/// aliases of nonlinear filters out of the audible band.
+///
+/// The inner filter is designed and updated through closures that get the oversampled rate, so
+/// its coefficients always match the rate it runs at.
+#[derive(Copy, Clone, Debug)]
+pub struct OversampledN<F: Processor, const N: usize> {
+    inner: F,
+    upsampler: UpsamplerN<F::Sample, N>,
+    downsampler: DownsamplerN<F::Sample, N>,
+    oversampling: OversamplingCoefficientsN<F::Sample, N>,
+    sample_rate_hz: F::Sample,
+}
+
+pub type Oversampled<F> = OversampledN<F, MAX_POLYPHASE_COEFFICIENTS>;
+
+impl<F: Processor, const N: usize> OversampledN<F, N> {
+    /// `design` builds the inner filter for the sample rate it's given, which is `sample_rate_hz`
+    /// times the oversampling factor
+    pub fn new<D: FnOnce(F::Sample) -> F>(
+        oversampling: OversamplingCoefficientsN<F::Sample, N>,
+        sample_rate_hz: F::Sample,
+        design: D,
+    ) -> OversampledN<F, N> {
+        let factor: F::Sample = (oversampling.factor() as u8).into();
+        OversampledN {
+            inner: design(sample_rate_hz * factor),
+            upsampler: UpsamplerN::new(&oversampling),
+            downsampler: DownsamplerN::new(&oversampling),
+            oversampling,
+            sample_rate_hz,
+        }
+    }
+
+    /// Calls `update` with the inner filter and the rate it runs at, for example to set new
+    /// coefficients designed for that rate
+    pub fn update<U: FnOnce(&mut F, F::Sample)>(&mut self, update: U) {
+        let rate = self.oversampled_rate_hz();
+        update(&mut self.inner, rate);
+    }
+
+    /// The inner filter, which can only be changed through `update`
+    pub fn inner(&self) -> &F {
+        &self.inner
+    }
+
+    pub fn process(&mut self, input_sample: F::Sample) -> F::Sample {
+        let factor = self.oversampling.factor();
+        let mut buffer = [input_sample; 1 << MAX_OVERSAMPLING_STAGES];
+        self.upsampler.process(input_sample, &mut buffer[..factor]);
+        for sample in buffer[..factor].iter_mut() {
+            *sample = self.inner.process(*sample);
+        }
+        self.downsampler.process(&buffer[..factor])
+    }
+
+    pub fn factor(&self) -> usize {
+        self.oversampling.factor()
+    }
+
+    pub fn oversampled_rate_hz(&self) -> F::Sample {
+        let factor: F::Sample = (self.oversampling.factor() as u8).into();
+        self.sample_rate_hz * factor
+    }
+
+    /// Delay of the half-band filters at DC in samples at the base rate, not counting the
+    /// inner filter
+    pub fn latency(&self) -> F::Sample {
+        self.oversampling.latency()
+    }
+
+    /// Clears the memory of the half-band filters and the inner filter
+    pub fn reset(&mut self) {
+        self.upsampler.reset();
+        self.downsampler.reset();
+        self.inner.reset();
+    }
+
+    pub fn state(&self) -> OversampledStateN<F::State, F::Sample, N> {
+        OversampledStateN {
+            inner: self.inner.state(),
+            upsampler: self.upsampler.state(),
+            downsampler: self.downsampler.state(),
+        }
+    }
+
+    pub fn set_state(&mut self, state: &OversampledStateN<F::State, F::Sample, N>) {
+        self.inner.set_state(&state.inner);
+        self.upsampler.set_state(&state.upsampler);
+        self.downsampler.set_state(&state.downsampler);
+    }
+}
+
+impl<F: Processor, const N: usize> Processor for OversampledN<F, N> {
+    type Sample = F::Sample;
+    type State = OversampledStateN<F::State, F::Sample, N>;
+    fn process(&mut self, input_sample: F::Sample) -> F::Sample {
+        OversampledN::process(self, input_sample)
+    }
+    fn reset(&mut self) {
+        OversampledN::reset(self)
+    }
+    fn state(&self) -> Self::State {
+        OversampledN::state(self)
+    }
+    fn set_state(&mut self, state: &Self::State) {
+        OversampledN::set_state(self, state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        filter_band::FilterBandCoefficients,
+        half_band::OversamplingCoefficients,
+        ladder::LadderCoefficients,
+        units::{Units, ZSample},
+    };
+
+    use super::*;
+
+    fn tone(frequency_hz: f64, fs: f64, i: usize) -> f64 {
+        (core::f64::consts::TAU * frequency_hz * i as f64 / fs).sin()
+    }
+
+    /// Amplitude of the steady state response to a tone, from the RMS over whole periods
+    fn amplitude<F: Processor<Sample = f64>>(filter: &mut F, frequency_hz: f64, fs: f64) -> f64 {
+        let squares: f64 = (0..9600)
+            .map(|i| filter.process(tone(frequency_hz, fs, i)))
+            .skip(4800)
+            .map(|y| y * y)
+            .sum();
+        (squares / 4800.0 * 2.0).sqrt()
+    }
+
+    #[test]
+    fn test_inner_runs_at_oversampled_rate() {
+        let fs = 48000.0;
+        let oversampling = OversamplingCoefficients::new(4, 100.0f64, 0.05);
+        let design = |rate| FilterBandCoefficients::lowpass_resonant(15000.0, 9.0, 2.0, rate);
+        let mut oversampled = Oversampled::new(oversampling, fs, |rate| {
+            assert_eq!(rate, 192000.0);
+            FilterBandN::from(&design(rate))
+        });
+        assert_eq!(oversampled.factor(), 4);
+        assert_eq!(oversampled.latency(), oversampling.latency());
+
+        // The resonance keeps its 192 kHz shape, where at 48 kHz it would be cramped
+        for f in [1000.0, 12000.0, 15000.0].iter() {
+            let expected = design(192000.0)
+                .get_bode_sample(ZSample::new(*f, 192000.0))
+                .norm();
+            oversampled.reset();
+            let measured = amplitude(&mut oversampled, *f, fs);
+            assert!((measured.lin_to_db() - expected.lin_to_db()).abs() < 0.05);
+        }
+
+        oversampled.update(|inner, rate| {
+            inner.update(&FilterBandCoefficients::lowpass(5000.0, 1.0, 2.0, rate))
+        });
+        oversampled.reset();
+        let measured = amplitude(&mut oversampled, 5000.0, fs);
+        assert!((measured.lin_to_db() + 3.0103).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_nonlinear_aliasing() {
+        // A driven ladder makes harmonics far above Nyquist. At 1x the third harmonic of 17 kHz
+        // folds back to 3 kHz, oversampled it's filtered out before the rate comes down.
+        let fs = 48000.0;
+        let coeffs = |rate| LadderCoefficients::driven(18000.0, 0.2, 4.0, rate);
+        let mut plain = Ladder::new(coeffs(fs));
+        let oversampling = OversamplingCoefficients::new(8, 100.0f64, 0.05);
+        let mut oversampled = Oversampled::new(oversampling, fs, |rate| Ladder::new(coeffs(rate)));
+
+        // Amplitude at 3 kHz from correlating over whole periods of both it and the tone
+        let folded_harmonic = |filter: &mut dyn FnMut(f64) -> f64| {
+            let (mut sin, mut cos) = (0.0f64, 0.0f64);
+            for i in 0..19200 {
+                let y = filter(tone(17000.0, fs, i));
+                if i >= 9600 {
+                    let phase = core::f64::consts::TAU * 3000.0 * i as f64 / fs;
+                    sin += y * phase.sin();
+                    cos += y * phase.cos();
+                }
+            }
+            (sin * sin + cos * cos).sqrt() * 2.0 / 9600.0
+        };
+        let plain_aliases = folded_harmonic(&mut |x| plain.process(x));
+        let oversampled_aliases = folded_harmonic(&mut |x| oversampled.process(x));
+        assert!(
+            oversampled_aliases.lin_to_db() < plain_aliases.lin_to_db() - 20.0,
+            "{} {}",
+            plain_aliases.lin_to_db(),
+            oversampled_aliases.lin_to_db()
+        );
+    }
+
+    #[test]
+    fn test_state() {
+        let fs = 48000.0;
+        let oversampling = OversamplingCoefficients::new(4, 90.0f64, 0.05);
+        let mut oversampled = Oversampled::new(oversampling, fs, |rate| {
+            Ladder::new(LadderCoefficients::driven(5000.0, 0.9, 4.0, rate))
+        });
+        for i in 0..500 {
+            oversampled.process(tone(1000.0, fs, i));
+        }
+        let state = oversampled.state();
+        let first: Vec<f64> = (500..1000)
+            .map(|i| oversampled.process(tone(1000.0, fs, i)))
+            .collect();
+        oversampled.set_state(&state);
+        let second: Vec<f64> = (500..1000)
+            .map(|i| oversampled.process(tone(1000.0, fs, i)))
+            .collect();
+        assert_eq!(first, second);
+    }
+}
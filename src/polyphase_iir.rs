@@ -0,0 +1,310 @@
+use num_complex::Complex;
+use num_traits::NumCast;
+
+use crate::{
+    error::FilterDesignError,
+    units::{ZSample, FP},
+};
+
+/// Most allpass coefficients in the default polyphase designs, enough for about 90 dB with a
+/// transition of 0.1% of the sample rate
+pub const MAX_POLYPHASE_COEFFICIENTS: usize = 16;
+
+/// Coefficients of a polyphase IIR half-band filter, two parallel chains of first order
+/// allpass sections in `z^-2`, designed with the elliptic method of Laurent de Soras' HIIR.
+///
+/// Even indices of `coefficients` belong to the first chain `A` and odd ones to the second chain
+/// `B`, and the half-band lowpass is `(A(z^2) + z^-1 B(z^2)) / 2`.
+/// `transition` is the width of the transition band around a quarter of the sample rate, as a
+/// fraction of the sample rate.
+#[derive(Copy, Clone, Debug)]
+pub struct PolyphaseCoefficientsN<T: FP, const N: usize> {
+    pub coefficients: [T; N],
+    pub count: usize,
+    pub transition: T,
+}
+
+pub type PolyphaseCoefficients<T> = PolyphaseCoefficientsN<T, MAX_POLYPHASE_COEFFICIENTS>;
+
+impl<T: FP, const N: usize> PolyphaseCoefficientsN<T, N> {
+    /// Response of the half-band lowpass
+    pub fn get_bode_sample(&self, z: ZSample<T>) -> Complex<T> {
+        //Use y.norm() for amplitude and y.arg().to_degrees() for phase. Add to combine phase.
+
+        let (a, b) = self.path_bode_samples(z, false);
+        (a + b * z.pow1) * T::N0_5
+    }
+
+    /// Responses of the two chains at `z`, with `z^-2` replaced by `-z^-2` if `shifted`
+    pub(crate) fn path_bode_samples(
+        &self,
+        z: ZSample<T>,
+        shifted: bool,
+    ) -> (Complex<T>, Complex<T>) {
+        let z2 = if shifted { -z.pow2 } else { z.pow2 };
+        let mut paths = [Complex::new(T::N1, T::N0); 2];
+        for (i, a) in self.coefficients[..self.count].iter().enumerate() {
+            paths[i % 2] = paths[i % 2] * (z2 + *a) / (z2 * *a + T::N1);
+        }
+        (paths[0], paths[1])
+    }
+
+    /// Group delay of the half-band lowpass at DC, in samples at the rate it runs
+    pub fn latency(&self) -> T {
+        // Each section in z^-2 delays DC by 2 * (1 - a) / (1 + a), and the lowpass averages
+        // the two chains, the second with one more sample of delay
+        let chains = self.coefficients[..self.count]
+            .iter()
+            .fold(T::N1, |delay, a| {
+                delay + T::N2 * (T::N1 - *a) / (T::N1 + *a)
+            });
+        chains * T::N0_5
+    }
+
+    /// Stopband attenuation reached by `count` coefficients at `transition`
+    pub fn attenuation_db(&self) -> T {
+        let (_, q) = transition_parameters(self.transition);
+        let order: T = NumCast::from(self.count * 2 + 1).unwrap();
+        let a = T::N4 * q.powf(order * T::N0_5);
+        let margin: T = ESTIMATE_MARGIN_DB.into();
+        -T::N10 * (a / (T::N1 + a)).log10() - margin
+    }
+
+    /// Fewest coefficients reaching `attenuation_db` with a transition band of `transition`
+    /// times the sample rate, between 0 and 0.5
+    pub fn coefficient_count(attenuation_db: T, transition: T) -> usize {
+        let (_, q) = transition_parameters(transition);
+        let margin: T = ESTIMATE_MARGIN_DB.into();
+        let attenuation_db = attenuation_db + margin;
+        let attenuation = T::N10.powf(-attenuation_db / T::N10);
+        let a = attenuation / (T::N1 - attenuation);
+        let order = ((a * a / 16.0f32.into()).ln() / q.ln()).ceil().max(T::N1);
+        // An even order is rounded up to the next odd one, 2 * count + 1
+        let order: usize = NumCast::from(order).unwrap_or(usize::MAX);
+        (order / 2).max(1)
+    }
+
+    /// Designs the filter with the fewest coefficients reaching `attenuation_db`, or with all `N`
+    /// if that's not enough
+    pub fn design(attenuation_db: T, transition: T) -> PolyphaseCoefficientsN<T, N> {
+        let count = PolyphaseCoefficientsN::<T, N>::coefficient_count(attenuation_db, transition);
+        PolyphaseCoefficientsN::from_count(count, transition)
+    }
+
+    /// Designs the filter with the steepest transition reaching `attenuation_db` with `count`
+    /// coefficients, or the full transition of 0.5 if no narrower one does
+    pub fn design_steepest(attenuation_db: T, count: usize) -> PolyphaseCoefficientsN<T, N> {
+        let count = count.max(1).min(N);
+        let (mut low, mut high) = (T::N0, T::N0_5);
+        for _ in 0..TRANSITION_ITERATIONS {
+            let middle = (low + high) * T::N0_5;
+            if PolyphaseCoefficientsN::<T, N>::coefficient_count(attenuation_db, middle) > count {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        PolyphaseCoefficientsN::from_count(count, high)
+    }
+
+    /// Designs the filter with `count` coefficients, clamped to `1..=N`
+    pub fn from_count(count: usize, transition: T) -> PolyphaseCoefficientsN<T, N> {
+        assert!(N > 0);
+        let count = count.max(1).min(N);
+        let transition = transition.max(T::epsilon()).min(T::N0_5 - T::epsilon());
+        let (k, q) = transition_parameters(transition);
+        let order = count * 2 + 1;
+        let mut coefficients = [T::N0; N];
+        for (index, coefficient) in coefficients[..count].iter_mut().enumerate() {
+            *coefficient = coefficient_at(index, k, q, order);
+        }
+        PolyphaseCoefficientsN {
+            coefficients,
+            count,
+            transition,
+        }
+    }
+
+    pub fn try_design(
+        attenuation_db: T,
+        transition: T,
+    ) -> Result<PolyphaseCoefficientsN<T, N>, FilterDesignError> {
+        if !(attenuation_db.is_finite() && transition.is_finite()) {
+            return Err(FilterDesignError::NonFiniteParameter);
+        }
+        if transition <= T::N0 || transition >= T::N0_5 {
+            return Err(FilterDesignError::InvalidBandEdges);
+        }
+        if PolyphaseCoefficientsN::<T, N>::coefficient_count(attenuation_db, transition) > N {
+            return Err(FilterDesignError::InvalidOrder);
+        }
+        Ok(PolyphaseCoefficientsN::design(attenuation_db, transition))
+    }
+}
+
+/// The elliptic estimate of the attenuation is up to about 0.2 dB optimistic for narrow
+/// transitions, so this much is kept in reserve
+const ESTIMATE_MARGIN_DB: f32 = 0.25;
+
+/// Bisection steps in `design_steepest`
+const TRANSITION_ITERATIONS: usize = 40;
+
+/// Terms of the theta function series in `coefficient_at`
+const SERIES_TERMS: i32 = 32;
+
+/// Selectivity `k` and nome `q` of the elliptic design
+fn transition_parameters<T: FP>(transition: T) -> (T, T) {
+    let k = ((T::N1 - transition * T::N2) * T::FRAC_PI_4()).tan();
+    let k = k * k;
+    let kksqrt = (T::N1 - k * k).powf(T::N0_5 * T::N0_5);
+    let e = T::N0_5 * (T::N1 - kksqrt) / (T::N1 + kksqrt);
+    let e2 = e * e;
+    let e4 = e2 * e2;
+    let c15: T = 15.0f32.into();
+    let c150: T = 150.0f32.into();
+    let q = e * (T::N1 + e4 * (T::N2 + e4 * (c15 + c150 * e4)));
+    (k, q)
+}
+
+fn coefficient_at<T: FP>(index: usize, k: T, q: T, order: usize) -> T {
+    let c: T = NumCast::from(index + 1).unwrap();
+    let order: T = NumCast::from(order).unwrap();
+
+    let mut numerator = T::N0;
+    let mut sign = T::N1;
+    for i in 0..SERIES_TERMS {
+        let i_t: T = NumCast::from(i).unwrap();
+        let q_power = q.powi(i * (i + 1));
+        numerator =
+            numerator + sign * q_power * ((i_t * T::N2 + T::N1) * c * T::PI() / order).sin();
+        sign = -sign;
+        // Not the whole term, whose sine may be zero long before the series has converged
+        if q_power < T::epsilon() {
+            break;
+        }
+    }
+    let mut denominator = T::N0_5;
+    let mut sign = -T::N1;
+    for i in 1..SERIES_TERMS {
+        let i_t: T = NumCast::from(i).unwrap();
+        let q_power = q.powi(i * i);
+        denominator = denominator + sign * q_power * (i_t * T::N2 * c * T::PI() / order).cos();
+        sign = -sign;
+        if q_power < T::epsilon() {
+            break;
+        }
+    }
+
+    let ww = numerator * q.powf(T::N0_5 * T::N0_5) / denominator;
+    let wwsq = ww * ww;
+    let x = ((T::N1 - wwsq * k) * (T::N1 - wwsq / k)).sqrt() / (T::N1 + wwsq);
+    (T::N1 - x) / (T::N1 + x)
+}
+
+/// Memory of the allpass sections in `z^-2` of both chains of a polyphase filter, indexed like
+/// the coefficients. The half-band resamplers run the same sections in `z^-1` at half the rate.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct AllpassSections<T: FP, const N: usize> {
+    x: [[T; 2]; N],
+    y: [[T; 2]; N],
+}
+
+impl<T: FP, const N: usize> AllpassSections<T, N> {
+    pub(crate) fn new() -> Self {
+        AllpassSections {
+            x: [[T::N0; 2]; N],
+            y: [[T::N0; 2]; N],
+        }
+    }
+
+    /// Runs `input_sample` through the chain starting at coefficient `first`. With `shifted`,
+    /// each section is `(a - z^-2) / (1 - a z^-2)`, the response moved up by a quarter of the
+    /// sample rate.
+    pub(crate) fn process_chain(
+        &mut self,
+        coeffs: &PolyphaseCoefficientsN<T, N>,
+        first: usize,
+        shifted: bool,
+        input_sample: T,
+    ) -> T {
+        let mut x = input_sample;
+        for i in (first..coeffs.count).step_by(2) {
+            let a = coeffs.coefficients[i];
+            let y = if shifted {
+                a * (x + self.y[i][1]) - self.x[i][1]
+            } else {
+                a * (x - self.y[i][1]) + self.x[i][1]
+            };
+            self.x[i] = [x, self.x[i][0]];
+            self.y[i] = [y, self.y[i][0]];
+            x = y;
+        }
+        x
+    }
+
+    /// Runs `input_sample` through the chain starting at coefficient `first`, with each section
+    /// in `z^-1` instead of `z^-2`. This is the chain run as one branch of a half-band filter at
+    /// half its rate, so only the most recent sample of memory is used.
+    pub(crate) fn process_chain_z1(
+        &mut self,
+        coeffs: &PolyphaseCoefficientsN<T, N>,
+        first: usize,
+        input_sample: T,
+    ) -> T {
+        let mut x = input_sample;
+        for i in (first..coeffs.count).step_by(2) {
+            let y = coeffs.coefficients[i] * (x - self.y[i][0]) + self.x[i][0];
+            self.x[i][0] = x;
+            self.y[i][0] = y;
+            x = y;
+        }
+        x
+    }
+
+    pub(crate) fn reset(&mut self) {
+        *self = AllpassSections::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::units::Units;
+
+    use super::*;
+
+    #[test]
+    fn test_half_band_design() {
+        let fs = 48000.0f64;
+        for (attenuation, transition) in [(60.0, 0.05), (90.0, 0.01), (40.0, 0.1)].iter() {
+            let coeffs = PolyphaseCoefficients::design(*attenuation, *transition);
+            assert!(coeffs.attenuation_db() >= *attenuation);
+            let pass_edge = (0.25 - transition * 0.5) * fs;
+            let stop_edge = (0.25 + transition * 0.5) * fs;
+            for i in 0..=100 {
+                let f = pass_edge * i as f64 / 100.0;
+                let db = coeffs
+                    .get_bode_sample(ZSample::new(f, fs))
+                    .norm()
+                    .lin_to_db();
+                assert!(db.abs() < 1e-3);
+                let f = stop_edge + (fs * 0.5 - stop_edge) * i as f64 / 100.0;
+                let db = coeffs
+                    .get_bode_sample(ZSample::new(f, fs))
+                    .norm()
+                    .lin_to_db();
+                assert!(db < -attenuation + 1e-6);
+            }
+            // One coefficient fewer is not enough
+            let fewer = PolyphaseCoefficients::from_count(coeffs.count - 1, *transition);
+            assert!(fewer.attenuation_db() < *attenuation);
+        }
+
+        let steep = PolyphaseCoefficients::design_steepest(80.0f64, 8);
+        assert_eq!(steep.count, 8);
+        assert!(steep.attenuation_db() >= 80.0 && steep.attenuation_db() < 81.0);
+        assert_eq!(
+            PolyphaseCoefficients::try_design(200.0f64, 0.001).unwrap_err(),
+            FilterDesignError::InvalidOrder
+        );
+    }
+}
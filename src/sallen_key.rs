@@ -0,0 +1,238 @@
+use num_complex::Complex;
+
+use crate::{saturation::Saturation, units::FP};
+
+/// Where a `SallenKey` filter saturates when it is given a `Saturation`. Both topologies have
+/// the same linear response.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SallenKeyTopology {
+    /// Saturates the summing node in front of the second stage, like an overdriven op-amp
+    SallenKey,
+    /// Saturates only the resonance feedback, like the diode limiter of the Korg35 in the MS-20
+    Korg35,
+}
+
+/// Coefficients for a two pole Sallen-Key lowpass or highpass made from three TPT one-pole
+/// stages: two in series, and one in the feedback path resolved without delay.
+///
+/// The response is `1 / (s^2 + (2 - k) * s + 1)` for the lowpass, with `k = 2 * resonance`,
+/// so the Q goes from 0.5 at no resonance to self-oscillation at a resonance of 1.
+#[derive(Copy, Clone, Debug)]
+pub struct SallenKeyCoefficients<T: FP> {
+    pub g: T,
+    pub a1: T,
+    pub k: T,
+    pub highpass: bool,
+}
+
+impl<T: FP> SallenKeyCoefficients<T> {
+    pub fn get_bode_sample(self, z: Complex<T>) -> Complex<T> {
+        //Use y.norm() for amplitude and y.arg().to_degrees() for phase. Add to combine phase.
+        //Ignores the saturation.
+
+        let denominator = self.g + z * (self.g - T::N1) + T::N1;
+        let lowpass = (z + T::N1) * self.g / denominator;
+        let highpass = -lowpass + T::N1;
+        let forward = if self.highpass {
+            highpass * highpass
+        } else {
+            lowpass * lowpass
+        };
+
+        forward / (-lowpass * highpass * self.k + T::N1)
+    }
+
+    pub fn lowpass(cutoff_hz: T, resonance: T, sample_rate_hz: T) -> SallenKeyCoefficients<T> {
+        SallenKeyCoefficients::new(cutoff_hz, resonance, false, sample_rate_hz)
+    }
+
+    pub fn highpass(cutoff_hz: T, resonance: T, sample_rate_hz: T) -> SallenKeyCoefficients<T> {
+        SallenKeyCoefficients::new(cutoff_hz, resonance, true, sample_rate_hz)
+    }
+
+    fn new(cutoff_hz: T, resonance: T, highpass: bool, sample_rate_hz: T) -> Self {
+        let cutoff_hz = cutoff_hz.min(sample_rate_hz * T::N0_5);
+        let g = (T::PI() * cutoff_hz / sample_rate_hz).tan();
+        let a1 = g / (T::N1 + g);
+        let k = T::N2 * resonance.max(T::N0);
+        SallenKeyCoefficients { g, a1, k, highpass }
+    }
+}
+
+/// Snapshot of the internal state of a `SallenKey`
+#[derive(Copy, Clone, Debug)]
+pub struct SallenKeyState<T: FP> {
+    ic1eq: [T; 3],
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct SallenKey<T: FP> {
+    /// States of the first stage, the second stage and the feedback stage
+    ic1eq: [T; 3],
+    topology: SallenKeyTopology,
+    saturation: Option<Saturation>,
+    pub coeffs: SallenKeyCoefficients<T>,
+}
+
+impl<T: FP> SallenKey<T> {
+    /// A linear filter if `saturation` is `None`
+    pub fn new(
+        coefficients: SallenKeyCoefficients<T>,
+        topology: SallenKeyTopology,
+        saturation: Option<Saturation>,
+    ) -> Self {
+        SallenKey {
+            ic1eq: [T::N0; 3],
+            topology,
+            saturation,
+            coeffs: coefficients,
+        }
+    }
+
+    pub fn process(&mut self, input_sample: T) -> T {
+        let a1 = self.coeffs.a1;
+        let b = T::N1 - a1;
+        let k = self.coeffs.k;
+        let highpass = self.coeffs.highpass;
+
+        let lowpass1 = tpt_lowpass(&mut self.ic1eq[0], a1, input_sample);
+        let stage1 = if highpass {
+            input_sample - lowpass1
+        } else {
+            lowpass1
+        };
+
+        // Outputs of the second and feedback stages are linear in their inputs, a one-pole
+        // lowpass giving a1 * input + (1 - a1) * ic1eq, which resolves the loop for the output
+        let s2 = b * self.ic1eq[1];
+        let s3 = b * self.ic1eq[2];
+        let denominator = T::N1 - k * a1 * b;
+        let (output, feedback) = if highpass {
+            let output = (b * stage1 + b * k * s3 - s2) / denominator;
+            (output, a1 * output + s3)
+        } else {
+            let output = (a1 * stage1 - a1 * k * s3 + s2) / denominator;
+            (output, b * output - s3)
+        };
+
+        let u = match (self.saturation, self.topology) {
+            (None, _) => stage1 + k * feedback,
+            (Some(saturation), SallenKeyTopology::SallenKey) => {
+                saturation.process(stage1 + k * feedback)
+            }
+            (Some(saturation), SallenKeyTopology::Korg35) => {
+                stage1 + saturation.process(k * feedback)
+            }
+        };
+        let output = if self.saturation.is_some() {
+            let lowpass2 = tpt_lowpass(&mut self.ic1eq[1], a1, u);
+            if highpass {
+                u - lowpass2
+            } else {
+                lowpass2
+            }
+        } else {
+            tpt_lowpass(&mut self.ic1eq[1], a1, u);
+            output
+        };
+        tpt_lowpass(&mut self.ic1eq[2], a1, output);
+        output
+    }
+
+    pub fn update_coefficients(&mut self, new_coefficients: SallenKeyCoefficients<T>) {
+        self.coeffs = new_coefficients;
+    }
+
+    pub fn set_saturation(&mut self, topology: SallenKeyTopology, saturation: Option<Saturation>) {
+        self.topology = topology;
+        self.saturation = saturation;
+    }
+
+    /// Clears the filter memory
+    pub fn reset(&mut self) {
+        self.ic1eq = [T::N0; 3];
+    }
+
+    pub fn state(&self) -> SallenKeyState<T> {
+        SallenKeyState { ic1eq: self.ic1eq }
+    }
+
+    pub fn set_state(&mut self, state: SallenKeyState<T>) {
+        self.ic1eq = state.ic1eq;
+    }
+}
+
+/// One step of a TPT one-pole lowpass, as in `IIR1`
+fn tpt_lowpass<T: FP>(ic1eq: &mut T, a1: T, input_sample: T) -> T {
+    let v1 = a1 * (input_sample - *ic1eq);
+    let v2 = v1 + *ic1eq;
+    *ic1eq = v2 + v1;
+    v2
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::units::ZSample;
+
+    use super::*;
+
+    fn impulse_response_at(
+        filter: &mut SallenKey<f64>,
+        frequency_hz: f64,
+        fs: f64,
+    ) -> Complex<f64> {
+        let z = ZSample::new(frequency_hz, fs).pow1;
+        let mut sum = Complex::new(0.0, 0.0);
+        let mut z_pow = Complex::new(1.0, 0.0);
+        for i in 0..20000 {
+            let y = filter.process(if i == 0 { 1.0 } else { 0.0 });
+            sum += z_pow * y;
+            z_pow *= z;
+        }
+        sum
+    }
+
+    #[test]
+    fn test_linear_response() {
+        let fs = 48000.0f64;
+        for coeffs in [
+            SallenKeyCoefficients::lowpass(1000.0, 0.7, fs),
+            SallenKeyCoefficients::highpass(1000.0, 0.7, fs),
+        ]
+        .iter()
+        {
+            // Q is 1 / (2 - k), which is the gain at the cutoff
+            let at_cutoff = coeffs.get_bode_sample(ZSample::new(1000.0, fs).pow1).norm();
+            assert!((at_cutoff - 1.0 / (2.0 - 1.4)).abs() < 1e-9);
+
+            let mut filter = SallenKey::new(*coeffs, SallenKeyTopology::SallenKey, None);
+            for f in [100.0, 1000.0, 5000.0].iter() {
+                filter.reset();
+                let measured = impulse_response_at(&mut filter, *f, fs);
+                let expected = coeffs.get_bode_sample(ZSample::new(*f, fs).pow1);
+                assert!((measured - expected).norm() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_saturated_self_oscillation() {
+        let fs = 48000.0f64;
+        let coeffs = SallenKeyCoefficients::lowpass(1000.0, 1.05, fs);
+        for topology in [SallenKeyTopology::SallenKey, SallenKeyTopology::Korg35].iter() {
+            let mut filter = SallenKey::new(coeffs, *topology, Some(Saturation::Tanh));
+            filter.process(0.1);
+            let tail: Vec<f64> = (0..48000).map(|_| filter.process(0.0)).collect();
+            let late_peak = tail[40000..].iter().fold(0.0f64, |m, y| m.max(y.abs()));
+            assert!(late_peak > 0.01 && late_peak < 10.0);
+
+            // Small signals see the linear filter
+            let mut linear = SallenKey::new(coeffs, *topology, None);
+            let mut saturated = SallenKey::new(coeffs, *topology, Some(Saturation::Cubic));
+            for i in 0..100 {
+                let x = 1e-6 * (i as f64 * 0.3).sin();
+                assert!((linear.process(x) - saturated.process(x)).abs() < 1e-12);
+            }
+        }
+    }
+}
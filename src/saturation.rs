@@ -0,0 +1,106 @@
+use crate::{units::FP, wide_units::WIDE};
+
+/// Soft clipping curves. All have unity slope at 0, so small signals pass unchanged.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Saturation {
+    /// `fast_tanh`, saturating at ±1
+    Tanh,
+    /// `x - 4x³/27`, reaching ±1 with zero slope at ±1.5. Brighter than tanh.
+    Cubic,
+    /// tanh for positive values, a softer curve saturating at -2 for negative ones, which adds
+    /// even harmonics
+    Asymmetric,
+}
+
+impl Saturation {
+    pub fn process<T: FP>(self, x: T) -> T {
+        match self {
+            Saturation::Tanh => fast_tanh(x),
+            Saturation::Cubic => {
+                let limit = T::N3 * T::N0_5;
+                let x = x.max(-limit).min(limit);
+                x - T::N4 * x * x * x / (T::N3 * T::N9)
+            }
+            Saturation::Asymmetric => {
+                if x >= T::N0 {
+                    fast_tanh(x)
+                } else {
+                    T::N2 * fast_tanh(x * T::N0_5)
+                }
+            }
+        }
+    }
+}
+
+/// Rational approximation of tanh, exact at 0 and reaching ±1 at ±3 where it is clamped.
+/// Within 0.025 of tanh everywhere.
+pub fn fast_tanh<T: FP>(x: T) -> T {
+    let x = x.max(-T::N3).min(T::N3);
+    let x2 = x * x;
+    let n27 = T::N3 * T::N9;
+    x * (n27 + x2) / (n27 + T::N9 * x2)
+}
+
+/// `fast_tanh(x) / x`, the gain of the saturator at `x`. 1 at 0, where it's defined by its
+/// limit, so drives of 0 give a linear gain.
+pub fn fast_tanh_ratio<T: FP>(x: T) -> T {
+    // Beyond ±3 fast_tanh is clamped to ±1, which the last factor takes care of
+    let clamped = x.abs().min(T::N3);
+    let x2 = clamped * clamped;
+    let n27 = T::N3 * T::N9;
+    (n27 + x2) / (n27 + T::N9 * x2) * T::N3 / x.abs().max(T::N3)
+}
+
+/// `WIDE` version of `fast_tanh_ratio`
+pub fn fast_tanh_ratio_w<T: WIDE>(x: T) -> T {
+    let clamped = x.abs().min(T::N3);
+    let x2 = clamped * clamped;
+    let n27 = T::N3 * T::N9;
+    (n27 + x2) / (n27 + T::N9 * x2) * T::N3 / x.abs().max(T::N3)
+}
+
+/// `WIDE` version of `fast_tanh`
+pub fn fast_tanh_w<T: WIDE>(x: T) -> T {
+    let x = x.max(T::N0 - T::N3).min(T::N3);
+    let x2 = x * x;
+    let n27 = T::N3 * T::N9;
+    x * (n27 + x2) / (n27 + T::N9 * x2)
+}
+
+#[cfg(test)]
+mod tests {
+    use wide::f32x4;
+
+    use super::*;
+
+    #[test]
+    fn test_fast_tanh() {
+        for i in -1000..=1000 {
+            let x = i as f64 * 0.01;
+            assert!((fast_tanh(x) - x.tanh()).abs() < 0.025);
+            let w: [f32; 4] = fast_tanh_w(f32x4::splat(x as f32)).into();
+            assert!((w[0] as f64 - fast_tanh(x)).abs() < 1e-6);
+            assert!((fast_tanh_ratio(x) * x - fast_tanh(x)).abs() < 1e-12);
+            let w: [f32; 4] = fast_tanh_ratio_w(f32x4::splat(x as f32)).into();
+            assert!((w[0] as f64 - fast_tanh_ratio(x)).abs() < 1e-6);
+        }
+        assert_eq!(fast_tanh(10.0), 1.0);
+        assert_eq!(fast_tanh_ratio(0.0f64), 1.0);
+    }
+
+    #[test]
+    fn test_saturation_curves() {
+        for saturation in [Saturation::Tanh, Saturation::Cubic, Saturation::Asymmetric].iter() {
+            assert!((saturation.process(1e-4f64) - 1e-4).abs() < 1e-10);
+            assert!((saturation.process(-1e-4f64) + 1e-4).abs() < 1e-10);
+            let mut last = saturation.process(-10.0f64);
+            for i in -999..=1000 {
+                let y = saturation.process(i as f64 * 0.01);
+                assert!(y >= last);
+                last = y;
+            }
+        }
+        assert_eq!(Saturation::Cubic.process(5.0), 1.0);
+        assert_eq!(Saturation::Asymmetric.process(-10.0), -2.0);
+    }
+}
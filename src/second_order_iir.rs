@@ -1,6 +1,12 @@
 use num_complex::Complex;
 
-use crate::{units::ZSample, MAX_CASCADE_COUNT};
+use crate::{
+    error::{validate_frequency, validate_q, FilterDesignError},
+    filter_band::{BellCharacter, FilterType},
+    first_order_iir::IIR1Coefficients,
+    units::{fast_db_to_gain, fast_prewarp, Units, ZSample},
+    MAX_CASCADE_COUNT,
+};
 
 use crate::units::FP;
 
@@ -34,6 +40,11 @@ impl<T: FP> IIR2Coefficients<T> {
         y
     }
 
+    /// Gain of the filter for a constant input
+    pub fn dc_gain(&self) -> T {
+        self.m0 + self.m2
+    }
+
     //TODO make const once possible
     pub fn empty() -> IIR2Coefficients<T> {
         IIR2Coefficients {
@@ -54,6 +65,116 @@ impl<T: FP> IIR2Coefficients<T> {
         [IIR2Coefficients::empty(); MAX_CASCADE_COUNT]
     }
 
+    /// Like `empty_cascade`, for a cascade of `N` stages
+    pub fn empty_cascade_n<const N: usize>() -> [IIR2Coefficients<T>; N] {
+        [IIR2Coefficients::empty(); N]
+    }
+
+    /// Coefficients with the same poles whose outputs are interpolated from the mix for `from` at
+    /// an `amount` of 0 to the mix for `to` at 1. Meant for coefficients designed as one of the
+    /// two types, so the shelf and bell gain `a` is shared.
+    pub fn morph(self, from: FilterType, to: FilterType, amount: T) -> IIR2Coefficients<T> {
+        let from = IIR2Coefficients::from_svf_parameters(from, self.a, self.g, self.k);
+        let to = IIR2Coefficients::from_svf_parameters(to, self.a, self.g, self.k);
+        IIR2Coefficients {
+            m0: from.m0 + (to.m0 - from.m0) * amount,
+            m1: from.m1 + (to.m1 - from.m1) * amount,
+            m2: from.m2 + (to.m2 - from.m2) * amount,
+            ..self
+        }
+    }
+
+    /// Combines two first order filters in series into a single second order filter with the
+    /// same response. `a` is set to 1, so the result can't be used with `from_svf_parameters`.
+    pub fn from_first_order_pair(
+        first: IIR1Coefficients<T>,
+        second: IIR1Coefficients<T>,
+    ) -> IIR2Coefficients<T> {
+        // Each stage is (m0 * s + g * (m0 + m1)) / (s + g)
+        let s2 = first.m0 * second.m0;
+        let s1 = first.m0 * second.g * (second.m0 + second.m1)
+            + second.m0 * first.g * (first.m0 + first.m1);
+        let s0 = first.g * (first.m0 + first.m1) * second.g * (second.m0 + second.m1);
+        let g = (first.g * second.g).sqrt();
+        let k = (first.g + second.g) / g;
+        let a1 = T::N1 / (T::N1 + g * (g + k));
+        let a2 = g * a1;
+        let a3 = g * a2;
+        let m0 = s2;
+        let m1 = (s1 - s2 * g * k) / g;
+        let m2 = s0 / (g * g) - s2;
+        IIR2Coefficients {
+            a: T::N1,
+            g,
+            gpow2: g * g,
+            k,
+            a1,
+            a2,
+            a3,
+            m0,
+            m1,
+            m2,
+        }
+    }
+
+    /// Recomputes the derived coefficients from `a`, `g` and `k` as stored by the constructor
+    /// for `filter_type`
+    pub fn from_svf_parameters(filter_type: FilterType, a: T, g: T, k: T) -> IIR2Coefficients<T> {
+        let (m0, m1, m2) = match filter_type {
+            FilterType::Lowpass => (T::N0, T::N0, T::N1),
+            FilterType::Highpass => (T::N1, -k, -T::N1),
+            FilterType::Bandpass => (T::N0, T::N1, T::N0),
+            FilterType::Notch => (T::N1, -k, T::N0),
+            FilterType::Allpass => (T::N1, -T::N2 * k, T::N0),
+            FilterType::Lowshelf => (T::N1, k * (a - T::N1), a * a - T::N1),
+            FilterType::Highshelf => (a * a, k * (T::N1 - a) * a, T::N1 - a * a),
+            FilterType::Bell => (T::N1, k * (a * a - T::N1), T::N0),
+        };
+        let a1 = T::N1 / (T::N1 + g * (g + k));
+        let a2 = g * a1;
+        let a3 = g * a2;
+        IIR2Coefficients {
+            a,
+            g,
+            gpow2: g * g,
+            k,
+            a1,
+            a2,
+            a3,
+            m0,
+            m1,
+            m2,
+        }
+    }
+
+    /// Designs coefficients using `fast_prewarp` and `fast_db_to_gain` in place of `tan` and
+    /// `powf`, for modulating a filter at audio rate. See those functions for the error bounds.
+    pub fn fast(
+        filter_type: FilterType,
+        cutoff_hz: T,
+        gain_db: T,
+        q_value: T,
+        sample_rate_hz: T,
+    ) -> IIR2Coefficients<T> {
+        let g = fast_prewarp(cutoff_hz, sample_rate_hz);
+        let (a, g, k) = match filter_type {
+            FilterType::Lowshelf => {
+                let a = fast_db_to_gain(gain_db, T::N40);
+                (a, g / a.sqrt(), T::N1 / q_value)
+            }
+            FilterType::Highshelf => {
+                let a = fast_db_to_gain(gain_db, T::N40);
+                (a, g * a.sqrt(), T::N1 / q_value)
+            }
+            FilterType::Bell => {
+                let a = fast_db_to_gain(gain_db, T::N40);
+                (a, g, T::N1 / (q_value * a))
+            }
+            _ => (T::N1, g, T::N1 / q_value),
+        };
+        IIR2Coefficients::from_svf_parameters(filter_type, a, g, k)
+    }
+
     pub fn lowpass(
         cutoff_hz: T,
         _gain_db: T,
@@ -252,11 +373,77 @@ impl<T: FP> IIR2Coefficients<T> {
             m2,
         }
     }
+    /// A lowpass with a resonant peak `resonance_db` above the passband instead of Q
+    pub fn lowpass_resonant(
+        cutoff_hz: T,
+        resonance_db: T,
+        sample_rate_hz: T,
+    ) -> IIR2Coefficients<T> {
+        let q_value = resonance_db.resonance_to_q();
+        IIR2Coefficients::lowpass(cutoff_hz, T::N0, q_value, sample_rate_hz)
+    }
+    /// A highpass with a resonant peak `resonance_db` above the passband instead of Q
+    pub fn highpass_resonant(
+        cutoff_hz: T,
+        resonance_db: T,
+        sample_rate_hz: T,
+    ) -> IIR2Coefficients<T> {
+        let q_value = resonance_db.resonance_to_q();
+        IIR2Coefficients::highpass(cutoff_hz, T::N0, q_value, sample_rate_hz)
+    }
+    /// A lowshelf with shelf slope `slope` instead of Q, see `Units::shelf_slope_to_q`
+    pub fn lowshelf_slope(
+        cutoff_hz: T,
+        gain_db: T,
+        slope: T,
+        sample_rate_hz: T,
+    ) -> IIR2Coefficients<T> {
+        let q_value = slope.shelf_slope_to_q(gain_db);
+        IIR2Coefficients::lowshelf(cutoff_hz, gain_db, q_value, sample_rate_hz)
+    }
+    /// A highshelf with shelf slope `slope` instead of Q, see `Units::shelf_slope_to_q`
+    pub fn highshelf_slope(
+        cutoff_hz: T,
+        gain_db: T,
+        slope: T,
+        sample_rate_hz: T,
+    ) -> IIR2Coefficients<T> {
+        let q_value = slope.shelf_slope_to_q(gain_db);
+        IIR2Coefficients::highshelf(cutoff_hz, gain_db, q_value, sample_rate_hz)
+    }
     pub fn bell(cutoff_hz: T, gain_db: T, q_value: T, sample_rate_hz: T) -> IIR2Coefficients<T> {
+        IIR2Coefficients::bell_with_character(
+            cutoff_hz,
+            gain_db,
+            q_value,
+            BellCharacter::Symmetric,
+            sample_rate_hz,
+        )
+    }
+    pub fn bell_with_character(
+        cutoff_hz: T,
+        gain_db: T,
+        q_value: T,
+        character: BellCharacter,
+        sample_rate_hz: T,
+    ) -> IIR2Coefficients<T> {
         let cutoff_hz = cutoff_hz.min(sample_rate_hz * T::N0_5);
         let a = T::N10.powf(gain_db / T::N40);
         let g = (T::PI() * cutoff_hz / sample_rate_hz).tan();
-        let k = T::N1 / (q_value * a);
+        let k = match character {
+            BellCharacter::Symmetric => T::N1 / (q_value * a),
+            // The poles set the peak of a boost and the zeros the dip of a cut
+            BellCharacter::ConstantQ => {
+                if a >= T::N1 {
+                    T::N1 / q_value
+                } else {
+                    T::N1 / (q_value * a * a)
+                }
+            }
+            BellCharacter::ProportionalQ => {
+                T::N1 / (q_value.proportional_q_to_symmetric_q(gain_db) * a)
+            }
+        };
         let a1 = T::N1 / (T::N1 + g * (g + k));
         let a2 = g * a1;
         let a3 = g * a2;
@@ -276,6 +463,156 @@ impl<T: FP> IIR2Coefficients<T> {
             m2,
         }
     }
+
+    pub fn try_lowpass(
+        cutoff_hz: T,
+        gain_db: T,
+        q_value: T,
+        sample_rate_hz: T,
+    ) -> Result<IIR2Coefficients<T>, FilterDesignError> {
+        validate_frequency(cutoff_hz, gain_db, sample_rate_hz)?;
+        validate_q(q_value)?;
+        Ok(IIR2Coefficients::lowpass(
+            cutoff_hz,
+            gain_db,
+            q_value,
+            sample_rate_hz,
+        ))
+    }
+
+    pub fn try_highpass(
+        cutoff_hz: T,
+        gain_db: T,
+        q_value: T,
+        sample_rate_hz: T,
+    ) -> Result<IIR2Coefficients<T>, FilterDesignError> {
+        validate_frequency(cutoff_hz, gain_db, sample_rate_hz)?;
+        validate_q(q_value)?;
+        Ok(IIR2Coefficients::highpass(
+            cutoff_hz,
+            gain_db,
+            q_value,
+            sample_rate_hz,
+        ))
+    }
+
+    pub fn try_bandpass(
+        cutoff_hz: T,
+        gain_db: T,
+        q_value: T,
+        sample_rate_hz: T,
+    ) -> Result<IIR2Coefficients<T>, FilterDesignError> {
+        validate_frequency(cutoff_hz, gain_db, sample_rate_hz)?;
+        validate_q(q_value)?;
+        Ok(IIR2Coefficients::bandpass(
+            cutoff_hz,
+            gain_db,
+            q_value,
+            sample_rate_hz,
+        ))
+    }
+
+    pub fn try_notch(
+        cutoff_hz: T,
+        gain_db: T,
+        q_value: T,
+        sample_rate_hz: T,
+    ) -> Result<IIR2Coefficients<T>, FilterDesignError> {
+        validate_frequency(cutoff_hz, gain_db, sample_rate_hz)?;
+        validate_q(q_value)?;
+        Ok(IIR2Coefficients::notch(
+            cutoff_hz,
+            gain_db,
+            q_value,
+            sample_rate_hz,
+        ))
+    }
+
+    pub fn try_allpass(
+        cutoff_hz: T,
+        gain_db: T,
+        q_value: T,
+        sample_rate_hz: T,
+    ) -> Result<IIR2Coefficients<T>, FilterDesignError> {
+        validate_frequency(cutoff_hz, gain_db, sample_rate_hz)?;
+        validate_q(q_value)?;
+        Ok(IIR2Coefficients::allpass(
+            cutoff_hz,
+            gain_db,
+            q_value,
+            sample_rate_hz,
+        ))
+    }
+
+    pub fn try_lowshelf(
+        cutoff_hz: T,
+        gain_db: T,
+        q_value: T,
+        sample_rate_hz: T,
+    ) -> Result<IIR2Coefficients<T>, FilterDesignError> {
+        validate_frequency(cutoff_hz, gain_db, sample_rate_hz)?;
+        validate_q(q_value)?;
+        Ok(IIR2Coefficients::lowshelf(
+            cutoff_hz,
+            gain_db,
+            q_value,
+            sample_rate_hz,
+        ))
+    }
+
+    pub fn try_highshelf(
+        cutoff_hz: T,
+        gain_db: T,
+        q_value: T,
+        sample_rate_hz: T,
+    ) -> Result<IIR2Coefficients<T>, FilterDesignError> {
+        validate_frequency(cutoff_hz, gain_db, sample_rate_hz)?;
+        validate_q(q_value)?;
+        Ok(IIR2Coefficients::highshelf(
+            cutoff_hz,
+            gain_db,
+            q_value,
+            sample_rate_hz,
+        ))
+    }
+
+    pub fn try_bell(
+        cutoff_hz: T,
+        gain_db: T,
+        q_value: T,
+        sample_rate_hz: T,
+    ) -> Result<IIR2Coefficients<T>, FilterDesignError> {
+        validate_frequency(cutoff_hz, gain_db, sample_rate_hz)?;
+        validate_q(q_value)?;
+        Ok(IIR2Coefficients::bell(
+            cutoff_hz,
+            gain_db,
+            q_value,
+            sample_rate_hz,
+        ))
+    }
+}
+
+/// Snapshot of the internal state of an `IIR2`
+#[derive(Copy, Clone, Debug)]
+pub struct IIR2State<T: FP> {
+    ic1eq: T,
+    ic2eq: T,
+}
+
+/// Every output of a SVF for one input sample, see `IIR2::process_multimode`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct IIR2Outputs<T> {
+    pub lowpass: T,
+    /// Bandpass with a peak gain of Q
+    pub bandpass: T,
+    /// Bandpass with unity peak gain
+    pub bandpass_normalized: T,
+    pub highpass: T,
+    pub notch: T,
+    /// Lowpass minus highpass
+    pub peak: T,
+    pub allpass: T,
 }
 
 /// Internal states and coefficients of the SVF form
@@ -316,7 +653,186 @@ impl<T: FP> IIR2<T> {
         (v1, v2)
     }
 
+    /// Processes one sample and returns all outputs of the SVF. The outputs use the `g` and
+    /// `k` of the coefficients and ignore `m0..m2`, so they are only meaningful for coefficients
+    /// designed as lowpass, highpass, bandpass, notch, peak or allpass.
+    pub fn process_multimode(&mut self, input_sample: T) -> IIR2Outputs<T> {
+        let (v1, v2) = self.process_partial(input_sample);
+        let k = self.coeffs.k;
+        let bandpass_normalized = k * v1;
+        let notch = input_sample - bandpass_normalized;
+        let highpass = notch - v2;
+        IIR2Outputs {
+            lowpass: v2,
+            bandpass: v1,
+            bandpass_normalized,
+            highpass,
+            notch,
+            peak: v2 - highpass,
+            allpass: notch - bandpass_normalized,
+        }
+    }
+
     pub fn update_coefficients(&mut self, new_coefficients: IIR2Coefficients<T>) {
         self.coeffs = new_coefficients;
     }
+
+    /// Clears the filter memory
+    pub fn reset(&mut self) {
+        self.ic1eq = T::N0;
+        self.ic2eq = T::N0;
+    }
+
+    /// Sets the filter memory to the steady state for a constant input of `value`
+    pub fn reset_to(&mut self, value: T) {
+        self.ic1eq = T::N0;
+        self.ic2eq = value;
+    }
+
+    pub fn state(&self) -> IIR2State<T> {
+        IIR2State {
+            ic1eq: self.ic1eq,
+            ic2eq: self.ic2eq,
+        }
+    }
+
+    pub fn set_state(&mut self, state: IIR2State<T>) {
+        self.ic1eq = state.ic1eq;
+        self.ic2eq = state.ic2eq;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn half_gain_bandwidth(coeffs: &IIR2Coefficients<f64>, gain_db: f64, fs: f64) -> f64 {
+        let db = |f| {
+            coeffs
+                .get_bode_sample(ZSample::new(f, fs))
+                .norm()
+                .lin_to_db()
+        };
+        let edge = |mut inside: f64, mut outside: f64| {
+            for _ in 0..60 {
+                let f = (inside * outside).sqrt();
+                if (db(f) - gain_db * 0.5) * gain_db > 0.0 {
+                    inside = f;
+                } else {
+                    outside = f;
+                }
+            }
+            inside
+        };
+        (edge(1000.0, fs * 0.5 - 1e-6) / edge(1000.0, 1.0)).log2()
+    }
+
+    #[test]
+    fn test_multimode() {
+        let fs = 48000.0;
+        let design = |filter_type| IIR2Coefficients::fast(filter_type, 1000.0, 0.0, 2.0, fs);
+        let mut multimode = IIR2::new(design(FilterType::Lowpass));
+        let mut filters = [
+            IIR2::new(design(FilterType::Lowpass)),
+            IIR2::new(design(FilterType::Highpass)),
+            IIR2::new(design(FilterType::Notch)),
+            IIR2::new(design(FilterType::Allpass)),
+        ];
+        for i in 0..1000 {
+            let x = ((i as f64 * 12.9898).sin() * 43758.5453).fract();
+            let outputs = multimode.process_multimode(x);
+            let expected: Vec<f64> = filters.iter_mut().map(|f| f.process(x)).collect();
+            assert!((outputs.lowpass - expected[0]).abs() < 1e-12);
+            assert!((outputs.highpass - expected[1]).abs() < 1e-12);
+            assert!((outputs.notch - expected[2]).abs() < 1e-12);
+            assert!((outputs.allpass - expected[3]).abs() < 1e-12);
+            assert!((outputs.bandpass_normalized - outputs.bandpass * 0.5).abs() < 1e-12);
+            assert!((outputs.peak - (outputs.lowpass - outputs.highpass)).abs() < 1e-12);
+        }
+
+        let lowpass = design(FilterType::Lowpass);
+        let half = lowpass.morph(FilterType::Lowpass, FilterType::Highpass, 0.5);
+        let highpass = design(FilterType::Highpass);
+        assert_eq!(half.a1, lowpass.a1);
+        assert!((half.m0 - 0.5 * highpass.m0).abs() < 1e-12);
+        assert!((half.m1 - 0.5 * highpass.m1).abs() < 1e-12);
+        assert!((half.m2 - 0.5 * (lowpass.m2 + highpass.m2)).abs() < 1e-12);
+        let full = lowpass.morph(FilterType::Lowpass, FilterType::Highpass, 1.0);
+        assert_eq!(
+            (full.m0, full.m1, full.m2),
+            (highpass.m0, highpass.m1, highpass.m2)
+        );
+    }
+
+    #[test]
+    fn test_bell_character() {
+        let fs = 48000.0f64;
+        let bell = |gain_db, character| {
+            IIR2Coefficients::bell_with_character(1000.0, gain_db, 1.0, character, fs)
+        };
+
+        for character in [
+            BellCharacter::Symmetric,
+            BellCharacter::ConstantQ,
+            BellCharacter::ProportionalQ,
+        ]
+        .iter()
+        {
+            // Cuts mirror boosts
+            let boost = bell(9.0, *character);
+            let cut = bell(-9.0, *character);
+            for f in [200.0, 1000.0, 3000.0].iter() {
+                let z = ZSample::new(*f, fs);
+                let y = boost.get_bode_sample(z) * cut.get_bode_sample(z);
+                assert!((y.norm() - 1.0).abs() < 1e-9);
+            }
+        }
+
+        let symmetric_3 = half_gain_bandwidth(&bell(3.0, BellCharacter::Symmetric), 3.0, fs);
+        let symmetric_15 = half_gain_bandwidth(&bell(15.0, BellCharacter::Symmetric), 15.0, fs);
+        assert!((symmetric_3 - symmetric_15).abs() < 1e-6);
+
+        let constant_3 = half_gain_bandwidth(&bell(3.0, BellCharacter::ConstantQ), 3.0, fs);
+        let constant_15 = half_gain_bandwidth(&bell(15.0, BellCharacter::ConstantQ), 15.0, fs);
+        assert!(constant_15 > constant_3);
+
+        let proportional_3 = half_gain_bandwidth(&bell(3.0, BellCharacter::ProportionalQ), 3.0, fs);
+        let proportional_15 =
+            half_gain_bandwidth(&bell(15.0, BellCharacter::ProportionalQ), 15.0, fs);
+        assert!(proportional_15 < proportional_3);
+        assert!(proportional_15 < symmetric_15);
+    }
+
+    #[test]
+    fn test_fast_coefficients() {
+        let fs = 48000.0;
+        let fast = IIR2Coefficients::fast(FilterType::Bell, 3000.0, 9.0, 2.0, fs);
+        let exact = IIR2Coefficients::bell(3000.0, 9.0, 2.0, fs);
+        for (x, y) in [fast.a1, fast.a2, fast.a3, fast.m1]
+            .iter()
+            .zip([exact.a1, exact.a2, exact.a3, exact.m1].iter())
+        {
+            assert!((x / y - 1.0f64).abs() < 1e-5);
+        }
+
+        let fast = IIR2Coefficients::fast(FilterType::Highshelf, 15000.0, -12.0, 0.7, fs);
+        let exact = IIR2Coefficients::highshelf(15000.0, -12.0, 0.7, fs);
+        for (x, y) in [fast.a1, fast.a2, fast.a3, fast.m0, fast.m1, fast.m2]
+            .iter()
+            .zip([exact.a1, exact.a2, exact.a3, exact.m0, exact.m1, exact.m2].iter())
+        {
+            assert!((x / y - 1.0f64).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_reset_to_steady_state() {
+        let coeffs =
+            IIR2Coefficients::lowpass(100.0, 0.0, core::f64::consts::FRAC_1_SQRT_2, 48000.0);
+        let mut filter = IIR2::new(coeffs);
+        filter.reset_to(0.5);
+        for _ in 0..100 {
+            assert!((filter.process(0.5) - 0.5f64).abs() < 1e-12);
+        }
+    }
 }
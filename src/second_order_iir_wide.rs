@@ -1,4 +1,10 @@
-use crate::{second_order_iir::IIR2Coefficients, units::FP, wide_units::WIDE, MAX_CASCADE_COUNT};
+use crate::{
+    filter_band::FilterType,
+    second_order_iir::{IIR2Coefficients, IIR2Outputs},
+    units::FP,
+    wide_units::{fast_db_to_gain_w, fast_prewarp_w, WIDE},
+    MAX_CASCADE_COUNT,
+};
 
 #[derive(Copy, Clone, Debug)]
 pub struct WideIIR2Coefficients<T: WIDE> {
@@ -39,6 +45,84 @@ impl<T: WIDE> WideIIR2Coefficients<T> {
         }
     }
 
+    /// Gain of the filter for a constant input
+    pub fn dc_gain(&self) -> T {
+        self.m0 + self.m2
+    }
+
+    /// `WIDE` version of `IIR2Coefficients::morph`, with a separate amount for each lane
+    pub fn morph(self, from: FilterType, to: FilterType, amount: T) -> WideIIR2Coefficients<T> {
+        let from = WideIIR2Coefficients::from_svf_parameters(from, self.a, self.g, self.k);
+        let to = WideIIR2Coefficients::from_svf_parameters(to, self.a, self.g, self.k);
+        WideIIR2Coefficients {
+            m0: from.m0 + (to.m0 - from.m0) * amount,
+            m1: from.m1 + (to.m1 - from.m1) * amount,
+            m2: from.m2 + (to.m2 - from.m2) * amount,
+            ..self
+        }
+    }
+
+    /// `WIDE` version of `IIR2Coefficients::from_svf_parameters`
+    pub fn from_svf_parameters(
+        filter_type: FilterType,
+        a: T,
+        g: T,
+        k: T,
+    ) -> WideIIR2Coefficients<T> {
+        let (m0, m1, m2) = match filter_type {
+            FilterType::Lowpass => (T::N0, T::N0, T::N1),
+            FilterType::Highpass => (T::N1, T::N0 - k, T::N0 - T::N1),
+            FilterType::Bandpass => (T::N0, T::N1, T::N0),
+            FilterType::Notch => (T::N1, T::N0 - k, T::N0),
+            FilterType::Allpass => (T::N1, T::N0 - T::N2 * k, T::N0),
+            FilterType::Lowshelf => (T::N1, k * (a - T::N1), a * a - T::N1),
+            FilterType::Highshelf => (a * a, k * (T::N1 - a) * a, T::N1 - a * a),
+            FilterType::Bell => (T::N1, k * (a * a - T::N1), T::N0),
+        };
+        let a1 = T::N1 / (T::N1 + g * (g + k));
+        let a2 = g * a1;
+        let a3 = g * a2;
+        WideIIR2Coefficients {
+            a,
+            g,
+            gpow2: g * g,
+            k,
+            a1,
+            a2,
+            a3,
+            m0,
+            m1,
+            m2,
+        }
+    }
+
+    /// `WIDE` version of `IIR2Coefficients::fast`, with separate parameters for each lane
+    pub fn fast(
+        filter_type: FilterType,
+        cutoff_hz: T,
+        gain_db: T,
+        q_value: T,
+        sample_rate_hz: T,
+    ) -> WideIIR2Coefficients<T> {
+        let g = fast_prewarp_w(cutoff_hz, sample_rate_hz);
+        let (a, g, k) = match filter_type {
+            FilterType::Lowshelf => {
+                let a = fast_db_to_gain_w(gain_db, T::N40);
+                (a, g / a.sqrt(), T::N1 / q_value)
+            }
+            FilterType::Highshelf => {
+                let a = fast_db_to_gain_w(gain_db, T::N40);
+                (a, g * a.sqrt(), T::N1 / q_value)
+            }
+            FilterType::Bell => {
+                let a = fast_db_to_gain_w(gain_db, T::N40);
+                (a, g, T::N1 / (q_value * a))
+            }
+            _ => (T::N1, g, T::N1 / q_value),
+        };
+        WideIIR2Coefficients::from_svf_parameters(filter_type, a, g, k)
+    }
+
     pub fn empty() -> WideIIR2Coefficients<T> {
         WideIIR2Coefficients {
             a: T::ZERO,
@@ -57,6 +141,18 @@ impl<T: WIDE> WideIIR2Coefficients<T> {
     pub fn empty_cascade() -> [WideIIR2Coefficients<T>; MAX_CASCADE_COUNT] {
         [WideIIR2Coefficients::<T>::empty(); MAX_CASCADE_COUNT]
     }
+
+    /// Like `empty_cascade`, for a cascade of `N` stages
+    pub fn empty_cascade_n<const N: usize>() -> [WideIIR2Coefficients<T>; N] {
+        [WideIIR2Coefficients::<T>::empty(); N]
+    }
+}
+
+/// Snapshot of the internal state of a `WideIIR2`
+#[derive(Copy, Clone, Debug)]
+pub struct WideIIR2State<T: WIDE> {
+    ic1eq: T,
+    ic2eq: T,
 }
 
 /// Internal states and coefficients of the SVF form
@@ -97,9 +193,50 @@ impl<T: WIDE> WideIIR2<T> {
         (v1, v2)
     }
 
+    /// `WIDE` version of `IIR2::process_multimode`
+    pub fn process_multimode(&mut self, input_sample: T) -> IIR2Outputs<T> {
+        let (v1, v2) = self.process_partial(input_sample);
+        let bandpass_normalized = self.coeffs.k * v1;
+        let notch = input_sample - bandpass_normalized;
+        let highpass = notch - v2;
+        IIR2Outputs {
+            lowpass: v2,
+            bandpass: v1,
+            bandpass_normalized,
+            highpass,
+            notch,
+            peak: v2 - highpass,
+            allpass: notch - bandpass_normalized,
+        }
+    }
+
     pub fn update_coefficients(&mut self, new_coefficients: WideIIR2Coefficients<T>) {
         self.coeffs = new_coefficients;
     }
+
+    /// Clears the filter memory
+    pub fn reset(&mut self) {
+        self.ic1eq = T::ZERO;
+        self.ic2eq = T::ZERO;
+    }
+
+    /// Sets the filter memory to the steady state for a constant input of `value`
+    pub fn reset_to(&mut self, value: T) {
+        self.ic1eq = T::ZERO;
+        self.ic2eq = value;
+    }
+
+    pub fn state(&self) -> WideIIR2State<T> {
+        WideIIR2State {
+            ic1eq: self.ic1eq,
+            ic2eq: self.ic2eq,
+        }
+    }
+
+    pub fn set_state(&mut self, state: WideIIR2State<T>) {
+        self.ic1eq = state.ic1eq;
+        self.ic2eq = state.ic2eq;
+    }
 }
 
 #[cfg(test)]
@@ -111,6 +248,42 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_wide_multimode() {
+        use crate::second_order_iir::IIR2;
+
+        let coeffs = IIR2Coefficients::bandpass(2000.0, 0.0, 3.0, 48000.0);
+        let mut filter = IIR2::new(coeffs);
+        let mut wide_filter = WideIIR2::<f64x4>::new(WideIIR2Coefficients::from(coeffs));
+        for i in 0..1000 {
+            let x = rand(i as f64);
+            let outputs = filter.process_multimode(x);
+            let wide_outputs = wide_filter.process_multimode(f64x4::splat(x));
+            for (y, w) in [outputs.highpass, outputs.peak, outputs.allpass]
+                .iter()
+                .zip(
+                    [
+                        wide_outputs.highpass,
+                        wide_outputs.peak,
+                        wide_outputs.allpass,
+                    ]
+                    .iter(),
+                )
+            {
+                let w: [f64; 4] = (*w).into();
+                assert!((w[0] - y).abs() < 1e-12);
+            }
+        }
+
+        let morphed = WideIIR2Coefficients::<f64x4>::from(coeffs).morph(
+            FilterType::Bandpass,
+            FilterType::Notch,
+            f64x4::from([0.0, 0.25, 0.5, 1.0]),
+        );
+        let m0: [f64; 4] = morphed.m0.into();
+        assert_eq!(m0, [0.0, 0.25, 0.5, 1.0]);
+    }
+
     fn rand(x: f64) -> f64 {
         ((x * 12.98983123).sin() * 43758.545345345).fract()
     }
@@ -142,4 +315,25 @@ mod tests {
         }
         println!("{} {} {} {}", ch1[500], ch2[500], ch3[500], ch4[500])
     }
+
+    #[test]
+    fn wide_fast_coefficients() {
+        let cutoffs = [100.0, 1000.0, 10000.0, 23000.0];
+        let gains = [-24.0, -3.0, 3.0, 24.0];
+        let coeffs: WideIIR2Coefficients<f64x4> = WideIIR2Coefficients::fast(
+            FilterType::Lowshelf,
+            f64x4::from(cutoffs),
+            f64x4::from(gains),
+            f64x4::from(0.7),
+            f64x4::from(48000.0),
+        );
+        let a1: [f64; 4] = coeffs.a1.into();
+        let m1: [f64; 4] = coeffs.m1.into();
+        for i in 0..4 {
+            let scalar =
+                IIR2Coefficients::fast(FilterType::Lowshelf, cutoffs[i], gains[i], 0.7, 48000.0);
+            assert!((a1[i] - scalar.a1).abs() < 1e-12);
+            assert!((m1[i] - scalar.m1).abs() < 1e-12);
+        }
+    }
 }
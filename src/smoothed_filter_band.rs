@@ -0,0 +1,255 @@
+use num_traits::NumCast;
+
+use crate::{
+    filter_band::{FilterBandCoefficientsN, FilterBandN, FilterType},
+    first_order_iir::IIR1Coefficients,
+    second_order_iir::IIR2Coefficients,
+    units::FP,
+    MAX_CASCADE_COUNT,
+};
+
+/// A `FilterBand` that glides to new cutoff, gain and bandwidth settings without zipper noise.
+///
+/// Rather than designing a full set of coefficients for every sample, the SVF parameters
+/// (`g`, `k` and the gain `a`) of each stage are ramped linearly towards the target and only the
+/// cheap derived terms (`a1..a3`, `m0..m2`) are recomputed. A change of order or filter type
+/// can't be ramped and is applied immediately.
+#[derive(Copy, Clone)]
+pub struct SmoothedFilterBandN<T: FP, const N: usize> {
+    pub filter: FilterBandN<T, N>,
+    filter_type: FilterType,
+    current: FilterBandCoefficientsN<T, N>,
+    target: FilterBandCoefficientsN<T, N>,
+    order: T,
+    sample_rate_hz: T,
+    update_interval: usize,
+    samples_until_update: usize,
+    updates_remaining: usize,
+}
+
+pub type SmoothedFilterBand<T> = SmoothedFilterBandN<T, MAX_CASCADE_COUNT>;
+
+impl<T: FP, const N: usize> SmoothedFilterBandN<T, N> {
+    pub fn new(
+        filter_type: FilterType,
+        cutoff_hz: T,
+        gain_db: T,
+        bandwidth_oct: T,
+        order: T,
+        sample_rate_hz: T,
+    ) -> SmoothedFilterBandN<T, N> {
+        let coeffs = FilterBandCoefficientsN::from_type(
+            filter_type,
+            cutoff_hz,
+            gain_db,
+            bandwidth_oct,
+            order,
+            sample_rate_hz,
+        );
+        SmoothedFilterBandN {
+            filter: FilterBandN::from(&coeffs),
+            filter_type,
+            current: coeffs,
+            target: coeffs,
+            order,
+            sample_rate_hz,
+            update_interval: 1,
+            samples_until_update: 0,
+            updates_remaining: 0,
+        }
+    }
+
+    /// Sets how many samples pass between coefficient updates while gliding. Defaults to 1.
+    pub fn set_update_interval(&mut self, samples: usize) {
+        self.update_interval = samples.max(1);
+    }
+
+    /// Starts a glide from the current settings to the given ones over `glide_ms`
+    pub fn set_target(&mut self, cutoff_hz: T, gain_db: T, bandwidth_oct: T, glide_ms: T) {
+        self.target = FilterBandCoefficientsN::from_type(
+            self.filter_type,
+            cutoff_hz,
+            gain_db,
+            bandwidth_oct,
+            self.order,
+            self.sample_rate_hz,
+        );
+        // Glides too long to count in samples never finish, rather than panicking
+        let glide_samples: usize = NumCast::from(
+            (glide_ms * self.sample_rate_hz / T::N1000)
+                .ceil()
+                .max(T::N0),
+        )
+        .unwrap_or(usize::MAX);
+        self.updates_remaining = glide_samples / self.update_interval;
+        self.samples_until_update = 0;
+        if self.updates_remaining == 0 {
+            self.current = self.target;
+            self.filter.update(&self.current);
+        }
+    }
+
+    pub fn is_settled(&self) -> bool {
+        self.updates_remaining == 0
+    }
+
+    /// Coefficients currently used by the filter
+    pub fn coefficients(&self) -> &FilterBandCoefficientsN<T, N> {
+        &self.current
+    }
+
+    pub fn process(&mut self, input_sample: T) -> T {
+        if self.updates_remaining > 0 {
+            if self.samples_until_update == 0 {
+                self.step();
+                self.samples_until_update = self.update_interval;
+            }
+            self.samples_until_update -= 1;
+        }
+        (self.filter.process)(&mut self.filter, input_sample)
+    }
+
+    fn step(&mut self) {
+        self.updates_remaining -= 1;
+        let structure_changed = self.current.iir1_enabled != self.target.iir1_enabled
+            || self.current.iir2_cascade_count != self.target.iir2_cascade_count;
+        if self.updates_remaining == 0 || structure_changed {
+            self.updates_remaining = 0;
+            self.current = self.target;
+        } else {
+            let steps: T = NumCast::from(self.updates_remaining + 1).unwrap();
+            let current = &mut self.current.iir1;
+            let target = &self.target.iir1;
+            *current = IIR1Coefficients::from_svf_parameters(
+                self.filter_type,
+                current.a + (target.a - current.a) / steps,
+                current.g + (target.g - current.g) / steps,
+            );
+            let iir2_count = self.current.iir2_cascade_count.max(1);
+            for (current, target) in self.current.iir2[..iir2_count]
+                .iter_mut()
+                .zip(self.target.iir2.iter())
+            {
+                *current = IIR2Coefficients::from_svf_parameters(
+                    self.filter_type,
+                    current.a + (target.a - current.a) / steps,
+                    current.g + (target.g - current.g) / steps,
+                    current.k + (target.k - current.k) / steps,
+                );
+            }
+        }
+        self.filter.update(&self.current);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        filter_band::FilterBandCoefficients,
+        units::{Units, ZSample},
+    };
+
+    use super::*;
+
+    fn rand(x: f32) -> f32 {
+        ((x * 12.9898).sin() * 43758.5453).fract()
+    }
+
+    #[test]
+    fn test_from_svf_parameters() {
+        let types = [
+            FilterType::Lowpass,
+            FilterType::Highpass,
+            FilterType::Bandpass,
+            FilterType::Notch,
+            FilterType::Allpass,
+            FilterType::Lowshelf,
+            FilterType::Highshelf,
+            FilterType::Bell,
+        ];
+        for filter_type in types.iter() {
+            let coeffs = FilterBandCoefficients::<f64>::from_type(
+                *filter_type,
+                1000.0,
+                6.0,
+                1.0,
+                5.0,
+                48000.0,
+            );
+            let count = coeffs.iir2_cascade_count.max(1);
+            for c in coeffs.iir2[..count].iter() {
+                let r = IIR2Coefficients::from_svf_parameters(*filter_type, c.a, c.g, c.k);
+                for (x, y) in [c.a1, c.a2, c.a3, c.m0, c.m1, c.m2]
+                    .iter()
+                    .zip([r.a1, r.a2, r.a3, r.m0, r.m1, r.m2].iter())
+                {
+                    assert!((x - y).abs() < 1e-12);
+                }
+            }
+            if coeffs.iir1_enabled {
+                let c = coeffs.iir1;
+                let r = IIR1Coefficients::from_svf_parameters(*filter_type, c.a, c.g);
+                assert!((c.a1 - r.a1).abs() < 1e-12);
+                assert!((c.m0 - r.m0).abs() < 1e-12);
+                assert!((c.m1 - r.m1).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_glide_reaches_target() {
+        let fs = 48000.0;
+        let mut filter = SmoothedFilterBand::new(FilterType::Highshelf, 1000.0, 6.0, 1.0, 4.0, fs);
+        filter.set_update_interval(16);
+        filter.set_target(4000.0, -6.0, 2.0, 10.0);
+        assert!(!filter.is_settled());
+
+        for i in 0..480 {
+            let y = filter.process(rand(i as f32));
+            assert!(y.is_finite());
+        }
+        assert!(filter.is_settled());
+
+        let target = FilterBandCoefficients::highshelf(4000.0, -6.0, 2.0, 4.0, fs);
+        for (c, t) in filter.coefficients().iir2.iter().zip(target.iir2.iter()) {
+            assert_eq!(c.a1, t.a1);
+            assert_eq!(c.m1, t.m1);
+        }
+    }
+
+    #[test]
+    fn test_unbounded_glide() {
+        let mut filter =
+            SmoothedFilterBand::new(FilterType::Lowpass, 1000.0, 0.0, 1.0, 2.0, 48000.0);
+        for glide_ms in [f32::INFINITY, 1e30].iter() {
+            filter.set_target(4000.0, 0.0, 1.0, *glide_ms);
+            for i in 0..100 {
+                assert!(filter.process(rand(i as f32)).is_finite());
+            }
+            assert!(!filter.is_settled());
+        }
+        filter.set_target(4000.0, 0.0, 1.0, f32::NAN);
+        assert!(filter.is_settled());
+    }
+
+    #[test]
+    fn test_new_matches_design() {
+        let fs = 48000.0f64;
+        for filter_type in [FilterType::Lowpass, FilterType::Bell].iter() {
+            for f in [500.0, 1000.0, 3000.0].iter() {
+                let mut filter = SmoothedFilterBand::new(*filter_type, 1000.0, 9.0, 1.0, 6.0, fs);
+                let squares: f64 = (0..9600)
+                    .map(|i| filter.process((core::f64::consts::TAU * f * i as f64 / fs).sin()))
+                    .skip(4800)
+                    .map(|y| y * y)
+                    .sum();
+                let amplitude = (squares / 4800.0 * 2.0).sqrt();
+                let expected = filter
+                    .coefficients()
+                    .get_bode_sample(ZSample::new(*f, fs))
+                    .norm();
+                assert!((amplitude.lin_to_db() - expected.lin_to_db()).abs() < 0.01);
+            }
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use num_traits::NumCast;
+
+use crate::{first_order_iir::IIR1Coefficients, units::FP};
+
+/// Glides a parameter towards a target with the TPT one-pole lowpass of `IIR1Coefficients`.
+///
+/// Once the value is within a relative tolerance of the target it snaps to it, after which
+/// `next` just returns the target.
+#[derive(Copy, Clone, Debug)]
+pub struct Smoother<T: FP> {
+    a1: T,
+    /// Integrator state relative to the target, which doesn't stall on rounding like the
+    /// absolute state would once the steps get small next to the value
+    error: T,
+    value: T,
+    target: T,
+    tolerance: T,
+    settled: bool,
+}
+
+impl<T: FP> Smoother<T> {
+    /// `time_ms` is the time constant, the time taken to cover about 63% of a step
+    pub fn new(initial_value: T, time_ms: T, sample_rate_hz: T) -> Smoother<T> {
+        Smoother {
+            a1: smoother_a1(time_ms, sample_rate_hz),
+            error: T::N0,
+            value: initial_value,
+            target: initial_value,
+            tolerance: NumCast::from(1e-5).unwrap(),
+            settled: true,
+        }
+    }
+
+    pub fn set_time(&mut self, time_ms: T, sample_rate_hz: T) {
+        self.a1 = smoother_a1(time_ms, sample_rate_hz);
+    }
+
+    /// Sets how close to the target, relative to the target's magnitude or 1 if that is
+    /// smaller, the value has to get before it snaps. Defaults to 1e-5.
+    pub fn set_tolerance(&mut self, tolerance: T) {
+        self.tolerance = tolerance;
+    }
+
+    pub fn set_target(&mut self, target: T) {
+        self.error = self.error + self.target - target;
+        self.target = target;
+        self.settled = self.value == target;
+    }
+
+    /// Jumps straight to `value` without gliding
+    pub fn set_immediate(&mut self, value: T) {
+        self.error = T::N0;
+        self.value = value;
+        self.target = value;
+        self.settled = true;
+    }
+
+    pub fn target(&self) -> T {
+        self.target
+    }
+
+    /// The last value returned by `next`
+    pub fn value(&self) -> T {
+        self.value
+    }
+
+    pub fn is_settled(&self) -> bool {
+        self.settled
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> T {
+        if self.settled {
+            return self.target;
+        }
+        let v1 = -self.a1 * self.error;
+        let error = v1 + self.error;
+        self.error = error + v1;
+        self.value = self.target + error;
+
+        let scale = self.target.abs().max(T::N1);
+        if error.abs() <= self.tolerance * scale {
+            self.set_immediate(self.target);
+        }
+        self.value
+    }
+
+    /// Fills `output` with successive values
+    pub fn fill(&mut self, output: &mut [T]) {
+        for sample in output.iter_mut() {
+            *sample = self.next();
+        }
+    }
+}
+
+/// `a1` of a TPT one-pole lowpass whose time constant is `time_ms`
+pub(crate) fn smoother_a1<T: FP>(time_ms: T, sample_rate_hz: T) -> T {
+    if time_ms <= T::N0 {
+        return T::N1;
+    }
+    let cutoff_hz = T::N1000 / (T::TAU() * time_ms);
+    IIR1Coefficients::lowpass(cutoff_hz, T::N0, sample_rate_hz).a1
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::first_order_iir::IIR1;
+
+    use super::*;
+
+    #[test]
+    fn test_smoother() {
+        let fs = 48000.0f32;
+        let mut smoother = Smoother::new(100.0, 10.0, fs);
+        assert!(smoother.is_settled());
+        smoother.set_target(1000.0);
+        assert!(!smoother.is_settled());
+
+        let cutoff = 1000.0 / (std::f32::consts::TAU * 10.0);
+        let mut lowpass = IIR1::new(IIR1Coefficients::lowpass(cutoff, 0.0, fs));
+        lowpass.reset_to(100.0);
+        let mut block = [0.0; 480];
+        smoother.fill(&mut block);
+        for y in block.iter() {
+            assert!((lowpass.process(1000.0) - y).abs() < 1e-2);
+        }
+        // One time constant covers about 63% of the step
+        assert!((block[479] - 100.0) / 900.0 > 0.62 && (block[479] - 100.0) / 900.0 < 0.64);
+
+        let mut steps = 0;
+        while !smoother.is_settled() {
+            smoother.next();
+            steps += 1;
+        }
+        assert!(steps < 48000);
+        assert_eq!(smoother.next(), 1000.0);
+
+        smoother.set_time(0.0, fs);
+        smoother.set_target(5.0);
+        assert_eq!(smoother.next(), 5.0);
+    }
+}
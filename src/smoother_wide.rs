@@ -0,0 +1,125 @@
+use num_traits::NumCast;
+
+use crate::{smoother::smoother_a1, wide_units::WIDE};
+
+/// `WIDE` version of `Smoother`, gliding each lane to its own target with a shared time
+/// constant. The smoother snaps once every lane is within tolerance.
+#[derive(Copy, Clone, Debug)]
+pub struct WideSmoother<T: WIDE> {
+    a1: T,
+    /// Integrator state relative to the target, which doesn't stall on rounding like the
+    /// absolute state would once the steps get small next to the value
+    error: T,
+    value: T,
+    target: T,
+    tolerance: T::Element,
+    settled: bool,
+}
+
+impl<T: WIDE> WideSmoother<T> {
+    /// `time_ms` is the time constant, the time taken to cover about 63% of a step
+    pub fn new(initial_value: T, time_ms: T::Element, sample_rate_hz: T::Element) -> Self {
+        WideSmoother {
+            a1: T::from_w(smoother_a1(time_ms, sample_rate_hz)),
+            error: T::ZERO,
+            value: initial_value,
+            target: initial_value,
+            tolerance: NumCast::from(1e-5).unwrap(),
+            settled: true,
+        }
+    }
+
+    pub fn set_time(&mut self, time_ms: T::Element, sample_rate_hz: T::Element) {
+        self.a1 = T::from_w(smoother_a1(time_ms, sample_rate_hz));
+    }
+
+    /// See `Smoother::set_tolerance`
+    pub fn set_tolerance(&mut self, tolerance: T::Element) {
+        self.tolerance = tolerance;
+    }
+
+    pub fn set_target(&mut self, target: T) {
+        self.error = self.error + self.target - target;
+        self.target = target;
+        self.settled = false;
+    }
+
+    /// Jumps straight to `value` without gliding
+    pub fn set_immediate(&mut self, value: T) {
+        self.error = T::ZERO;
+        self.value = value;
+        self.target = value;
+        self.settled = true;
+    }
+
+    pub fn target(&self) -> T {
+        self.target
+    }
+
+    /// The last value returned by `next`
+    pub fn value(&self) -> T {
+        self.value
+    }
+
+    pub fn is_settled(&self) -> bool {
+        self.settled
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> T {
+        if self.settled {
+            return self.target;
+        }
+        let v1 = self.a1 * (T::ZERO - self.error);
+        let error = v1 + self.error;
+        self.error = error + v1;
+        self.value = self.target + error;
+
+        // Squared error relative to the target's magnitude or 1, whichever is larger
+        let relative_error = error * error / (self.target * self.target + T::N1);
+        let limit = self.tolerance * self.tolerance;
+        let mut settled = true;
+        relative_error.for_each_lane(|_, e| settled &= e <= limit);
+        if settled {
+            self.set_immediate(self.target);
+        }
+        self.value
+    }
+
+    /// Fills `output` with successive values
+    pub fn fill(&mut self, output: &mut [T]) {
+        for sample in output.iter_mut() {
+            *sample = self.next();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wide::f64x2;
+
+    use crate::smoother::Smoother;
+
+    use super::*;
+
+    #[test]
+    fn test_wide_smoother() {
+        let fs = 48000.0;
+        let mut left = Smoother::new(0.0, 5.0, fs);
+        let mut right = Smoother::new(10.0, 5.0, fs);
+        let mut wide_smoother = WideSmoother::new(f64x2::from([0.0, 10.0]), 5.0, fs);
+        left.set_target(1.0);
+        right.set_target(-10.0);
+        wide_smoother.set_target(f64x2::from([1.0, -10.0]));
+        for _ in 0..240 {
+            let y: [f64; 2] = wide_smoother.next().into();
+            assert!((y[0] - left.next()).abs() < 1e-12);
+            assert!((y[1] - right.next()).abs() < 1e-12);
+        }
+        while !wide_smoother.is_settled() {
+            wide_smoother.next();
+        }
+        let y: [f64; 2] = wide_smoother.next().into();
+        assert_eq!(y, [1.0, -10.0]);
+    }
+}
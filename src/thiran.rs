@@ -0,0 +1,281 @@
+use num_complex::Complex;
+use num_traits::NumCast;
+
+use crate::{
+    error::FilterDesignError,
+    units::{ZSample, FP},
+};
+
+/// Highest order of the default `Thiran`
+pub const MAX_THIRAN_ORDER: usize = 8;
+
+/// Coefficients for a Thiran allpass, a fractional delay with a flat magnitude and a maximally
+/// flat group delay at DC.
+///
+/// The transfer function is `z^-order * A(1/z) / A(z)` with
+/// `A(z) = 1 + a[0] z^-1 + ... + a[order - 1] z^-order`. It's stable for delays above
+/// `order - 1` and most accurate between `order - 0.5` and `order + 0.5`.
+#[derive(Copy, Clone, Debug)]
+pub struct ThiranCoefficientsN<T: FP, const N: usize> {
+    pub a: [T; N],
+    pub order: usize,
+    /// Group delay at DC in samples
+    pub delay: T,
+}
+
+pub type ThiranCoefficients<T> = ThiranCoefficientsN<T, MAX_THIRAN_ORDER>;
+
+impl<T: FP, const N: usize> ThiranCoefficientsN<T, N> {
+    pub fn get_bode_sample(&self, z: ZSample<T>) -> Complex<T> {
+        //Use y.norm() for amplitude and y.arg().to_degrees() for phase. Add to combine phase.
+
+        let z = z.pow1;
+        let mut numerator = Complex::new(T::N0, T::N0);
+        let mut denominator = Complex::new(T::N1, T::N0);
+        let mut z_pow = Complex::new(T::N1, T::N0);
+        for k in 1..=self.order {
+            numerator = numerator + z_pow * self.a[self.order - k];
+            z_pow = z_pow * z;
+            denominator = denominator + z_pow * self.a[k - 1];
+        }
+        numerator = numerator + z_pow;
+        numerator / denominator
+    }
+
+    /// Group delay in samples at `frequency_hz`
+    pub fn group_delay(&self, frequency_hz: T, sample_rate_hz: T) -> T {
+        // The phase is -order * w - 2 * arg(A), so the group delay is order minus twice the
+        // group delay of the denominator
+        let z = ZSample::new(frequency_hz, sample_rate_hz).pow1;
+        let mut denominator = Complex::new(T::N1, T::N0);
+        let mut weighted = Complex::new(T::N0, T::N0);
+        let mut z_pow = Complex::new(T::N1, T::N0);
+        for k in 1..=self.order {
+            z_pow = z_pow * z;
+            let k_t: T = NumCast::from(k).unwrap();
+            denominator = denominator + z_pow * self.a[k - 1];
+            weighted = weighted + z_pow * self.a[k - 1] * k_t;
+        }
+        let order: T = NumCast::from(self.order).unwrap();
+        order - T::N2 * (weighted / denominator).re
+    }
+
+    /// Phase delay in samples at `frequency_hz`
+    pub fn phase_delay(&self, frequency_hz: T, sample_rate_hz: T) -> T {
+        let omega = T::TAU() * frequency_hz / sample_rate_hz;
+        let phase = self
+            .get_bode_sample(ZSample::new(frequency_hz, sample_rate_hz))
+            .arg();
+        // Unwrap against the expected delay, as the phase passes -PI well within the band
+        let turns = ((self.delay * omega + phase) / T::TAU()).round();
+        (turns * T::TAU() - phase) / omega
+    }
+
+    /// Thiran allpass of `order` giving `delay_samples` of delay at DC. `order` is clamped to
+    /// `1..=N` and the delay to at least `order - 0.5`.
+    pub fn new(delay_samples: T, order: usize) -> ThiranCoefficientsN<T, N> {
+        assert!(N > 0);
+        let order = order.max(1).min(N);
+        let order_t: T = NumCast::from(order).unwrap();
+        let delay = delay_samples.max(order_t - T::N0_5);
+
+        let mut a = [T::N0; N];
+        let mut binomial = T::N1;
+        for k in 1..=order {
+            let k_t: T = NumCast::from(k).unwrap();
+            binomial = binomial * (order_t - k_t + T::N1) / k_t;
+            let mut product = T::N1;
+            for n in 0..=order {
+                let n_t: T = NumCast::from(n).unwrap();
+                product = product * (delay - order_t + n_t) / (delay - order_t + k_t + n_t);
+            }
+            let sign = if k % 2 == 1 { -T::N1 } else { T::N1 };
+            a[k - 1] = sign * binomial * product;
+        }
+        ThiranCoefficientsN { a, order, delay }
+    }
+
+    /// Uses the highest order up to `N` that suits `delay_samples`
+    pub fn from_delay(delay_samples: T) -> ThiranCoefficientsN<T, N> {
+        // Clamped before the cast, which fails for infinite or huge delays
+        let max_order: T = NumCast::from(N).unwrap();
+        let order = (delay_samples + T::N0_5).floor().max(T::N1).min(max_order);
+        let order: usize = NumCast::from(order).unwrap();
+        ThiranCoefficientsN::new(delay_samples, order)
+    }
+
+    pub fn try_new(
+        delay_samples: T,
+        order: usize,
+    ) -> Result<ThiranCoefficientsN<T, N>, FilterDesignError> {
+        if !delay_samples.is_finite() {
+            return Err(FilterDesignError::NonFiniteParameter);
+        }
+        if order < 1 || order > N {
+            return Err(FilterDesignError::InvalidOrder);
+        }
+        let order_t: T = NumCast::from(order).unwrap();
+        if delay_samples < order_t - T::N0_5 {
+            return Err(FilterDesignError::InvalidDelay);
+        }
+        Ok(ThiranCoefficientsN::new(delay_samples, order))
+    }
+}
+
+/// Snapshot of the internal state of a `Thiran`
+#[derive(Copy, Clone, Debug)]
+pub struct ThiranStateN<T: FP, const N: usize> {
+    x: [T; N],
+    y: [T; N],
+}
+
+pub type ThiranState<T> = ThiranStateN<T, MAX_THIRAN_ORDER>;
+
+/// Direct form Thiran allpass holding the last `N` inputs and outputs
+#[derive(Copy, Clone, Debug)]
+pub struct ThiranN<T: FP, const N: usize> {
+    x: [T; N],
+    y: [T; N],
+    pub coeffs: ThiranCoefficientsN<T, N>,
+}
+
+pub type Thiran<T> = ThiranN<T, MAX_THIRAN_ORDER>;
+
+impl<T: FP, const N: usize> ThiranN<T, N> {
+    pub fn new(coefficients: ThiranCoefficientsN<T, N>) -> Self {
+        ThiranN {
+            x: [T::N0; N],
+            y: [T::N0; N],
+            coeffs: coefficients,
+        }
+    }
+
+    pub fn process(&mut self, input_sample: T) -> T {
+        let order = self.coeffs.order;
+        let a = &self.coeffs.a;
+        let mut output = a[order - 1] * input_sample;
+        for k in 1..=order {
+            let b = if k == order { T::N1 } else { a[order - k - 1] };
+            output = output + b * self.x[k - 1] - a[k - 1] * self.y[k - 1];
+        }
+        for k in (1..order).rev() {
+            self.x[k] = self.x[k - 1];
+            self.y[k] = self.y[k - 1];
+        }
+        self.x[0] = input_sample;
+        self.y[0] = output;
+        output
+    }
+
+    /// Keeps the history, so small delay changes don't click. Changing the order resets it.
+    pub fn update_coefficients(&mut self, new_coefficients: ThiranCoefficientsN<T, N>) {
+        if new_coefficients.order != self.coeffs.order {
+            self.reset();
+        }
+        self.coeffs = new_coefficients;
+    }
+
+    /// Clears the filter memory
+    pub fn reset(&mut self) {
+        self.x = [T::N0; N];
+        self.y = [T::N0; N];
+    }
+
+    pub fn state(&self) -> ThiranStateN<T, N> {
+        ThiranStateN {
+            x: self.x,
+            y: self.y,
+        }
+    }
+
+    pub fn set_state(&mut self, state: &ThiranStateN<T, N>) {
+        self.x = state.x;
+        self.y = state.y;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_delay() {
+        let fs = 48000.0f64;
+        for order in 1..=MAX_THIRAN_ORDER {
+            for offset in [-0.4, 0.0, 0.3].iter() {
+                let delay = order as f64 + offset;
+                let coeffs = ThiranCoefficients::new(delay, order);
+                assert!((coeffs.group_delay(1.0, fs) - delay).abs() < 1e-6);
+                assert!((coeffs.phase_delay(1.0, fs) - delay).abs() < 1e-6);
+                assert!((coeffs.group_delay(2000.0, fs) - delay).abs() < 0.02);
+                for f in [100.0, 5000.0, 20000.0].iter() {
+                    let y = coeffs.get_bode_sample(ZSample::new(*f, fs));
+                    assert!((y.norm() - 1.0).abs() < 1e-9);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_matches_bode() {
+        let fs = 48000.0f64;
+        let coeffs = ThiranCoefficients::new(4.37, 4);
+        let mut filter = Thiran::new(coeffs);
+        for f in [100.0, 3000.0, 15000.0].iter() {
+            filter.reset();
+            let z = ZSample::new(*f, fs).pow1;
+            let mut measured = Complex::new(0.0, 0.0);
+            let mut z_pow = Complex::new(1.0, 0.0);
+            for i in 0..2000 {
+                measured += z_pow * filter.process(if i == 0 { 1.0 } else { 0.0 });
+                z_pow *= z;
+            }
+            let expected = coeffs.get_bode_sample(ZSample::new(*f, fs));
+            assert!((measured - expected).norm() < 1e-9);
+        }
+
+        // First order matches the textbook (1 - d) / (1 + d)
+        let first = ThiranCoefficients::new(0.8f64, 1);
+        assert!((first.a[0] - 0.2 / 1.8).abs() < 1e-12);
+        assert_eq!(ThiranCoefficients::from_delay(5.6f64).order, 6);
+        assert_eq!(
+            ThiranCoefficients::from_delay(f64::INFINITY).order,
+            MAX_THIRAN_ORDER
+        );
+        assert_eq!(
+            ThiranCoefficients::from_delay(1e30f32).order,
+            MAX_THIRAN_ORDER
+        );
+    }
+
+    #[test]
+    fn test_try_new() {
+        assert_eq!(
+            ThiranCoefficients::try_new(2.0f32, 4).unwrap_err(),
+            FilterDesignError::InvalidDelay
+        );
+        assert_eq!(
+            ThiranCoefficients::try_new(20.0f32, 9).unwrap_err(),
+            FilterDesignError::InvalidOrder
+        );
+        assert_eq!(
+            ThiranCoefficients::try_new(f32::NAN, 2).unwrap_err(),
+            FilterDesignError::NonFiniteParameter
+        );
+        assert!(ThiranCoefficients::try_new(3.6f32, 4).is_ok());
+    }
+
+    #[test]
+    fn test_state() {
+        let mut filter = Thiran::new(ThiranCoefficients::new(4.37f32, 4));
+        let input = |i: usize| ((i as f32 * 12.9898).sin() * 43758.5453).fract();
+        for i in 0..500 {
+            filter.process(input(i));
+        }
+        let state = filter.state();
+        let first: Vec<f32> = (500..1000).map(|i| filter.process(input(i))).collect();
+        filter.set_state(&state);
+        let second: Vec<f32> = (500..1000).map(|i| filter.process(input(i))).collect();
+        assert_eq!(first, second);
+    }
+}
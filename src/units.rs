@@ -36,6 +36,7 @@ pub trait FP:
     const N10: Self;
     const N20: Self;
     const N40: Self;
+    const N1000: Self;
     const BUTTERWORTH: [[Self; 32]; 32];
 }
 
@@ -55,6 +56,7 @@ impl FP for f32 {
     const N10: f32 = 10.0;
     const N20: f32 = 20.0;
     const N40: f32 = 40.0;
+    const N1000: f32 = 1000.0;
     const BUTTERWORTH: [[f32; 32]; 32] = CONST_BUTTERWORTHF32;
 }
 
@@ -74,6 +76,7 @@ impl FP for f64 {
     const N10: f64 = 10.0;
     const N20: f64 = 20.0;
     const N40: f64 = 40.0;
+    const N1000: f64 = 1000.0;
     const BUTTERWORTH: [[f64; 32]; 32] = CONST_BUTTERWORTHF64;
 }
 
@@ -85,7 +88,29 @@ pub trait Units<T> {
     fn db_to_lin(self) -> T;
     fn lin_to_db(self) -> T;
     fn sign(self, b: T) -> T;
+    /// Q for a bandwidth in octaves measured on the digital frequency axis around `f0`, so the
+    /// band edges land where the bode plot shows them after bilinear warping. The edges are the
+    /// -3dB points of a bandpass or notch, and the half gain points of a bell.
     fn bandwidth_to_q(self, f0: T, fs: T) -> T;
+    /// Inverse of `bandwidth_to_q`, giving the bandwidth in octaves on the digital frequency axis
+    fn q_to_bandwidth(self, f0: T, fs: T) -> T;
+    /// Analog bandwidth in octaves to Q, ignoring frequency warping
+    fn octaves_to_q(self) -> T;
+    /// Analog Q to bandwidth in octaves, ignoring frequency warping
+    fn q_to_octaves(self) -> T;
+    /// Q of a `BellCharacter::Symmetric` bell with the same shape as a
+    /// `BellCharacter::ProportionalQ` bell of Q `self`, which narrows as `gain_db` moves away
+    /// from 0
+    fn proportional_q_to_symmetric_q(self, gain_db: T) -> T;
+    /// Inverse of `proportional_q_to_symmetric_q`
+    fn symmetric_q_to_proportional_q(self, gain_db: T) -> T;
+    /// Q of a second order shelf of `gain_db` with shelf slope `self`. A slope of 1 is the
+    /// steepest without overshoot, higher slopes add a bump and dip around the transition.
+    /// Slopes past `(A + 1/A) / (A + 1/A - 2)`, where `A = 10^(gain_db / 40)`, give NaN.
+    fn shelf_slope_to_q(self, gain_db: T) -> T;
+    /// Q of a second order lowpass or highpass whose resonant peak is `self` dB above the
+    /// passband. Resonances of 0dB or less give the Butterworth Q.
+    fn resonance_to_q(self) -> T;
 }
 
 impl<T: FP> Units<T> for T {
@@ -108,12 +133,72 @@ impl<T: FP> Units<T> for T {
             self
         }
     }
-    fn bandwidth_to_q(self, _f0: T, _fs: T) -> T {
+    fn bandwidth_to_q(self, f0: T, fs: T) -> T {
+        // The prewarped band edges sit a factor `e^s` either side of the prewarped center,
+        // while the digital edges are `self` octaves apart. Solve for `s` with Newton's method,
+        // starting from the larger of two lower bounds: the analog half width, which warping
+        // only widens, and the width that puts the upper edge at Nyquist.
+        let t0 = (T::PI() * f0 / fs).tan();
+        if t0 <= T::N0 {
+            return self.octaves_to_q();
+        }
+        let ln_ratio = T::LN_2() * self;
+        let bound = (ln_ratio * T::N0_5).max((t0 / (T::FRAC_PI_2() / T::N2.powf(self)).tan()).ln());
+        let mut s = bound;
+        for _ in 0..BANDWIDTH_ITERATIONS {
+            let upper = t0 * s.exp();
+            let lower = t0 * (-s).exp();
+            let (upper_angle, lower_angle) = (upper.atan(), lower.atan());
+            let error = (upper_angle / lower_angle).ln() - ln_ratio;
+            let slope = T::N1 / ((upper.recip() + upper) * upper_angle)
+                + T::N1 / ((lower.recip() + lower) * lower_angle);
+            let step = error / slope;
+            s = (s - step).max(bound);
+            // Convergence is quadratic, so this step already landed within rounding error
+            if step.abs() <= T::epsilon().sqrt() * s {
+                break;
+            }
+        }
+        T::N1 / (T::N2 * s.sinh())
+    }
+    fn q_to_bandwidth(self, f0: T, fs: T) -> T {
+        let t0 = (T::PI() * f0 / fs).tan();
+        if t0 <= T::N0 {
+            return self.q_to_octaves();
+        }
+        let width = t0 / self;
+        let upper = (width + (width * width + T::N2 * T::N2 * t0 * t0).sqrt()) * T::N0_5;
+        let lower = t0 * t0 / upper;
+        (upper.atan() / lower.atan()).log2()
+    }
+    fn octaves_to_q(self) -> T {
         let two = T::N2;
         T::N1 / (two * (T::LN_2() / two * self).sinh())
     }
+    fn q_to_octaves(self) -> T {
+        let two = T::N2;
+        two / T::LN_2() * (T::N1 / (two * self)).asinh()
+    }
+    fn proportional_q_to_symmetric_q(self, gain_db: T) -> T {
+        self * T::N10.powf(gain_db.abs() / T::N40)
+    }
+    fn symmetric_q_to_proportional_q(self, gain_db: T) -> T {
+        self / T::N10.powf(gain_db.abs() / T::N40)
+    }
+    fn shelf_slope_to_q(self, gain_db: T) -> T {
+        let a = T::N10.powf(gain_db / T::N40);
+        T::N1 / ((a + T::N1 / a) * (T::N1 / self - T::N1) + T::N2).sqrt()
+    }
+    fn resonance_to_q(self) -> T {
+        // The peak of Q / sqrt(1 - 1 / (4 * Q^2)) solved for Q
+        let peak = self.max(T::N0).db_to_lin();
+        ((peak * peak + peak * (peak * peak - T::N1).sqrt()) * T::N0_5).sqrt()
+    }
 }
 
+/// Most Newton steps in `bandwidth_to_q`. It takes at most 4 for bandwidths up to 4 octaves.
+const BANDWIDTH_ITERATIONS: usize = 8;
+
 #[derive(Copy, Clone, Debug)]
 pub struct ZSample<T> {
     pub pow1: Complex<T>,
@@ -132,6 +217,53 @@ impl<T: FP> ZSample<T> {
     }
 }
 
+/// Rational approximation of `tan(x)` for `0 <= x < PI / 2`, cheap enough to prewarp a cutoff
+/// for every sample.
+///
+/// Uses a [5/4] Padé approximant at `x / 2` followed by the double angle identity. The relative
+/// error, not counting the rounding of `T`, is below 4e-7 for `x <= 0.49 * PI` and below 5e-6 for
+/// `x <= 0.499 * PI`.
+pub fn fast_tan<T: FP>(x: T) -> T {
+    let c15: T = 15.0f32.into();
+    let c105: T = 105.0f32.into();
+    let c420: T = 420.0f32.into();
+    let c945: T = 945.0f32.into();
+    let y = x * T::N0_5;
+    let y2 = y * y;
+    let n = y * (c945 + y2 * (y2 - c105));
+    let d = c945 + y2 * (c15 * y2 - c420);
+    T::N2 * n * d / (d * d - n * n)
+}
+
+/// Polynomial approximation of `e^x`, used for fast dB conversions.
+///
+/// Evaluates a 5th order Taylor series at `x / 16` and squares the result four times. The
+/// relative error, not counting the rounding of `T`, is below 1e-6 for `|x| <= 2.8` (±48 dB for
+/// `10^(dB/40)`) and below 6e-5 for `|x| <= 5.5`.
+pub fn fast_exp<T: FP>(x: T) -> T {
+    let y = x / 16.0f32.into();
+    let mut p = T::N1
+        + y * (T::N1
+            + y * (T::N0_5
+                + y * (T::N1 / T::N6 + y * (T::N1 / 24.0f32.into() + y / 120.0f32.into()))));
+    for _ in 0..4 {
+        p = p * p;
+    }
+    p
+}
+
+/// Fast approximation of `tan(PI * cutoff_hz / sample_rate_hz)`, with the cutoff limited to
+/// `0.499 * sample_rate_hz`. See `fast_tan` for the error bounds.
+pub fn fast_prewarp<T: FP>(cutoff_hz: T, sample_rate_hz: T) -> T {
+    let max_ratio: T = 0.499f32.into();
+    fast_tan(T::PI() * (cutoff_hz / sample_rate_hz).min(max_ratio))
+}
+
+/// Fast approximation of `10^(gain_db / divisor)`. See `fast_exp` for the error bounds.
+pub fn fast_db_to_gain<T: FP>(gain_db: T, divisor: T) -> T {
+    fast_exp(gain_db * T::LN_10() / divisor)
+}
+
 //the output of this is stored as const [[T; 32]; 32] in const_butterworth.rs
 pub fn butterworth_cascade_q<T: FP>(filter_order: usize, pole: usize) -> T {
     let filter_order = NumCast::from(filter_order).unwrap();
@@ -158,6 +290,67 @@ mod tests {
 
     use super::*;
 
+    fn edge_frequency(
+        coeffs: &crate::second_order_iir::IIR2Coefficients<f64>,
+        level_db: f64,
+        mut inside: f64,
+        mut outside: f64,
+        fs: f64,
+    ) -> f64 {
+        let db = |f| {
+            coeffs
+                .get_bode_sample(ZSample::new(f, fs))
+                .norm()
+                .lin_to_db()
+        };
+        let rising = db(inside) > db(outside);
+        for _ in 0..60 {
+            let f = (inside * outside).sqrt();
+            if (db(f) > level_db) == rising {
+                inside = f;
+            } else {
+                outside = f;
+            }
+        }
+        inside
+    }
+
+    #[test]
+    fn test_warped_bandwidth() {
+        use crate::second_order_iir::IIR2Coefficients;
+        let fs = 48000.0;
+        for f0 in [1000.0, 8000.0, 16000.0].iter() {
+            for bw in [0.5, 1.0, 2.0].iter() {
+                let q = bw.bandwidth_to_q(*f0, fs);
+                assert!((q.q_to_bandwidth(*f0, fs) - bw).abs() < 1e-9);
+
+                // The bandpass peaks at Q, so its edges are 3dB below that
+                let coeffs = IIR2Coefficients::bandpass(*f0, 0.0, q, fs);
+                let edge_db = q.lin_to_db() - 3.0103;
+                let low = edge_frequency(&coeffs, edge_db, *f0, 1.0, fs);
+                let high = edge_frequency(&coeffs, edge_db, *f0, fs * 0.5 - 1e-6, fs);
+                assert!(((high / low).log2() - bw).abs() < 1e-6);
+
+                let coeffs = IIR2Coefficients::bell(*f0, 12.0, q, fs);
+                let low = edge_frequency(&coeffs, 6.0, *f0, 1.0, fs);
+                let high = edge_frequency(&coeffs, 6.0, *f0, fs * 0.5 - 1e-6, fs);
+                assert!(((high / low).log2() - bw).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bandwidth_conventions() {
+        let q = 2.0f64.octaves_to_q();
+        assert!((q - 0.6667).abs() < 1e-4);
+        assert!((q.q_to_octaves() - 2.0).abs() < 1e-12);
+        assert!((1.0f64.bandwidth_to_q(10.0, 48000.0) - 1.0.octaves_to_q()).abs() < 1e-6);
+
+        let symmetric_q = 1.0f64.proportional_q_to_symmetric_q(-12.0);
+        assert!((symmetric_q - 1.9953).abs() < 1e-4);
+        assert!((symmetric_q.symmetric_q_to_proportional_q(-12.0) - 1.0).abs() < 1e-12);
+    }
+
     #[test]
     fn test_butterworth_cascade_q() {
         assert_eq!(0.7071067811865475, butterworth_cascade_q(2, 0));
@@ -177,4 +370,23 @@ mod tests {
         assert_eq!(1.931851652578135, butterworth_cascade_q(6, 2));
         dbg!(butterworth_cascade_q::<f64>(5, 2));
     }
+
+    #[test]
+    fn test_fast_tan() {
+        for i in 1..10000 {
+            let x = 0.49 * std::f64::consts::PI * i as f64 / 10000.0;
+            assert!((fast_tan(x) / x.tan() - 1.0).abs() < 4e-7);
+        }
+        let x = 0.499 * std::f64::consts::PI;
+        assert!((fast_tan(x) / x.tan() - 1.0).abs() < 5e-6);
+    }
+
+    #[test]
+    fn test_fast_db_to_gain() {
+        for i in -480..=480 {
+            let gain_db = i as f64 * 0.1;
+            let exact = 10.0f64.powf(gain_db / 40.0);
+            assert!((fast_db_to_gain(gain_db, 40.0) / exact - 1.0).abs() < 1e-6);
+        }
+    }
 }
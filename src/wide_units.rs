@@ -74,7 +74,19 @@ pub trait WIDE:
     + Sub<Self, Output = Self>
     + Div<Self, Output = Self>
 {
+    /// Scalar type held in each lane
+    type Element: FP;
+    /// Number of lanes in the vector
+    const LANES: usize;
     fn from_w<T: FP>(n: T) -> Self;
+    /// Builds a vector by calling `f` with each lane index
+    fn from_fn<F: FnMut(usize) -> Self::Element>(f: F) -> Self;
+    /// Calls `f` with the index and value of each lane
+    fn for_each_lane<F: FnMut(usize, Self::Element)>(self, f: F);
+    fn min(self, rhs: Self) -> Self;
+    fn max(self, rhs: Self) -> Self;
+    fn abs(self) -> Self;
+    fn sqrt(self) -> Self;
     const ZERO: Self;
     const ONE: Self;
     const N0: Self;
@@ -95,11 +107,40 @@ pub trait WIDE:
 }
 
 impl WIDE for f64x4 {
+    type Element = f64;
+    const LANES: usize = 4;
     #[inline]
     fn from_w<T: FP>(n: T) -> f64x4 {
         let n: f64 = NumCast::from(n).unwrap();
         Self::from(n)
     }
+    #[inline]
+    fn from_fn<F: FnMut(usize) -> f64>(f: F) -> f64x4 {
+        Self::from(core::array::from_fn::<f64, 4, F>(f))
+    }
+    #[inline]
+    fn for_each_lane<F: FnMut(usize, f64)>(self, mut f: F) {
+        let lanes: [f64; 4] = self.into();
+        for (i, lane) in lanes.iter().enumerate() {
+            f(i, *lane);
+        }
+    }
+    #[inline]
+    fn min(self, rhs: f64x4) -> f64x4 {
+        f64x4::min(self, rhs)
+    }
+    #[inline]
+    fn max(self, rhs: f64x4) -> f64x4 {
+        f64x4::max(self, rhs)
+    }
+    #[inline]
+    fn abs(self) -> f64x4 {
+        f64x4::abs(self)
+    }
+    #[inline]
+    fn sqrt(self) -> f64x4 {
+        f64x4::sqrt(self)
+    }
     const ZERO: f64x4 = f64x4::ZERO;
     const ONE: f64x4 = f64x4::ONE;
 
@@ -120,11 +161,40 @@ impl WIDE for f64x4 {
     const_f64_as_f64x4!(N40, 40.0);
 }
 impl WIDE for f64x2 {
+    type Element = f64;
+    const LANES: usize = 2;
     #[inline]
     fn from_w<T: FP>(n: T) -> f64x2 {
         let n: f64 = NumCast::from(n).unwrap();
         Self::from(n)
     }
+    #[inline]
+    fn from_fn<F: FnMut(usize) -> f64>(f: F) -> f64x2 {
+        Self::from(core::array::from_fn::<f64, 2, F>(f))
+    }
+    #[inline]
+    fn for_each_lane<F: FnMut(usize, f64)>(self, mut f: F) {
+        let lanes: [f64; 2] = self.into();
+        for (i, lane) in lanes.iter().enumerate() {
+            f(i, *lane);
+        }
+    }
+    #[inline]
+    fn min(self, rhs: f64x2) -> f64x2 {
+        f64x2::min(self, rhs)
+    }
+    #[inline]
+    fn max(self, rhs: f64x2) -> f64x2 {
+        f64x2::max(self, rhs)
+    }
+    #[inline]
+    fn abs(self) -> f64x2 {
+        f64x2::abs(self)
+    }
+    #[inline]
+    fn sqrt(self) -> f64x2 {
+        f64x2::sqrt(self)
+    }
     const ZERO: f64x2 = f64x2::ZERO;
     const ONE: f64x2 = f64x2::ONE;
 
@@ -145,11 +215,40 @@ impl WIDE for f64x2 {
     const_f64_as_f64x2!(N40, 40.0);
 }
 impl WIDE for f32x8 {
+    type Element = f32;
+    const LANES: usize = 8;
     #[inline]
     fn from_w<T: FP>(n: T) -> f32x8 {
         let n: f32 = NumCast::from(n).unwrap();
         Self::from(n)
     }
+    #[inline]
+    fn from_fn<F: FnMut(usize) -> f32>(f: F) -> f32x8 {
+        Self::from(core::array::from_fn::<f32, 8, F>(f))
+    }
+    #[inline]
+    fn for_each_lane<F: FnMut(usize, f32)>(self, mut f: F) {
+        let lanes: [f32; 8] = self.into();
+        for (i, lane) in lanes.iter().enumerate() {
+            f(i, *lane);
+        }
+    }
+    #[inline]
+    fn min(self, rhs: f32x8) -> f32x8 {
+        f32x8::min(self, rhs)
+    }
+    #[inline]
+    fn max(self, rhs: f32x8) -> f32x8 {
+        f32x8::max(self, rhs)
+    }
+    #[inline]
+    fn abs(self) -> f32x8 {
+        f32x8::abs(self)
+    }
+    #[inline]
+    fn sqrt(self) -> f32x8 {
+        f32x8::sqrt(self)
+    }
     const ZERO: f32x8 = f32x8::ZERO;
     const ONE: f32x8 = f32x8::ONE;
     const_f32_as_f32x8!(N0, 0.0);
@@ -170,11 +269,40 @@ impl WIDE for f32x8 {
 }
 
 impl WIDE for f32x4 {
+    type Element = f32;
+    const LANES: usize = 4;
     #[inline]
     fn from_w<T: FP>(n: T) -> f32x4 {
         let n: f32 = NumCast::from(n).unwrap();
         Self::from(n)
     }
+    #[inline]
+    fn from_fn<F: FnMut(usize) -> f32>(f: F) -> f32x4 {
+        Self::from(core::array::from_fn::<f32, 4, F>(f))
+    }
+    #[inline]
+    fn for_each_lane<F: FnMut(usize, f32)>(self, mut f: F) {
+        let lanes: [f32; 4] = self.into();
+        for (i, lane) in lanes.iter().enumerate() {
+            f(i, *lane);
+        }
+    }
+    #[inline]
+    fn min(self, rhs: f32x4) -> f32x4 {
+        f32x4::min(self, rhs)
+    }
+    #[inline]
+    fn max(self, rhs: f32x4) -> f32x4 {
+        f32x4::max(self, rhs)
+    }
+    #[inline]
+    fn abs(self) -> f32x4 {
+        f32x4::abs(self)
+    }
+    #[inline]
+    fn sqrt(self) -> f32x4 {
+        f32x4::sqrt(self)
+    }
     const ZERO: f32x4 = f32x4::ZERO;
     const ONE: f32x4 = f32x4::ONE;
     const_f32_as_f32x4!(N0, 0.0);
@@ -193,3 +321,40 @@ impl WIDE for f32x4 {
     const_f32_as_f32x4!(N20, 20.0);
     const_f32_as_f32x4!(N40, 40.0);
 }
+
+/// `WIDE` version of `units::fast_tan`, with the same error bounds
+pub fn fast_tan_w<T: WIDE>(x: T) -> T {
+    let c15 = T::from_w(15.0);
+    let c105 = T::from_w(105.0);
+    let c420 = T::from_w(420.0);
+    let c945 = T::from_w(945.0);
+    let y = x * T::N0_5;
+    let y2 = y * y;
+    let n = y * (c945 + y2 * (y2 - c105));
+    let d = c945 + y2 * (c15 * y2 - c420);
+    T::N2 * n * d / (d * d - n * n)
+}
+
+/// `WIDE` version of `units::fast_exp`, with the same error bounds
+pub fn fast_exp_w<T: WIDE>(x: T) -> T {
+    let y = x / T::from_w(16.0);
+    let mut p = T::N1
+        + y * (T::N1
+            + y * (T::N0_5
+                + y * (T::N1 / T::N6 + y * (T::N1 / T::from_w(24.0) + y / T::from_w(120.0)))));
+    for _ in 0..4 {
+        p = p * p;
+    }
+    p
+}
+
+/// `WIDE` version of `units::fast_prewarp`
+pub fn fast_prewarp_w<T: WIDE>(cutoff_hz: T, sample_rate_hz: T) -> T {
+    let max_ratio = T::from_w(0.499);
+    fast_tan_w(T::from_w(core::f64::consts::PI) * (cutoff_hz / sample_rate_hz).min(max_ratio))
+}
+
+/// `WIDE` version of `units::fast_db_to_gain`
+pub fn fast_db_to_gain_w<T: WIDE>(gain_db: T, divisor: T) -> T {
+    fast_exp_w(gain_db * T::from_w(core::f64::consts::LN_10) / divisor)
+}